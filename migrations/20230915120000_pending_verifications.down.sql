@@ -0,0 +1 @@
+DROP TABLE pending_verifications;
\ No newline at end of file
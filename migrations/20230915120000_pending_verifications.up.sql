@@ -0,0 +1,8 @@
+CREATE TABLE pending_verifications (
+	id TEXT PRIMARY KEY,
+	username TEXT NOT NULL,
+	code TEXT NOT NULL,
+	method SMALLINT NOT NULL,
+	generated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+	expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,3 @@
+ALTER TABLE pending_verifications
+	DROP COLUMN interaction_token,
+	DROP COLUMN locale;
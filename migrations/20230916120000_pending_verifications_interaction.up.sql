@@ -0,0 +1,3 @@
+ALTER TABLE pending_verifications
+	ADD COLUMN interaction_token TEXT,
+	ADD COLUMN locale TEXT;
@@ -0,0 +1,7 @@
+CREATE TABLE role_rules (
+	guild_id TEXT NOT NULL,
+	role_id TEXT NOT NULL,
+	condition SMALLINT NOT NULL,
+	value BIGINT NOT NULL,
+	PRIMARY KEY (guild_id, role_id)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE guild_config (
+	guild_id TEXT PRIMARY KEY,
+	log_channel_id TEXT,
+	ephemeral BOOLEAN NOT NULL DEFAULT false,
+	locale TEXT,
+	disabled_commands TEXT[] NOT NULL DEFAULT '{}'
+);
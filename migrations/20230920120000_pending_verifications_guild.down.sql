@@ -0,0 +1,2 @@
+ALTER TABLE pending_verifications
+	DROP COLUMN guild_id;
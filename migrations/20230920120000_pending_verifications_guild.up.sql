@@ -0,0 +1,2 @@
+ALTER TABLE pending_verifications
+	ADD COLUMN guild_id TEXT;
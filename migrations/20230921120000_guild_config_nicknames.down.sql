@@ -0,0 +1,2 @@
+ALTER TABLE guild_config
+	DROP COLUMN sync_nicknames;
@@ -0,0 +1,2 @@
+ALTER TABLE guild_config
+	ADD COLUMN sync_nicknames BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+ALTER TABLE scratch_accounts
+	DROP COLUMN scratch_id;
@@ -0,0 +1,4 @@
+ALTER TABLE scratch_accounts
+	ADD COLUMN scratch_id BIGINT;
+
+CREATE UNIQUE INDEX ON scratch_accounts (scratch_id);
@@ -0,0 +1,3 @@
+ALTER TABLE scratch_accounts
+	DROP COLUMN linked_at,
+	DROP COLUMN verified_via;
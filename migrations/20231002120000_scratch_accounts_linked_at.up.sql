@@ -0,0 +1,3 @@
+ALTER TABLE scratch_accounts
+	ADD COLUMN linked_at TIMESTAMP WITH TIME ZONE,
+	ADD COLUMN verified_via SMALLINT;
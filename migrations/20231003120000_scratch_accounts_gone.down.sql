@@ -0,0 +1,3 @@
+ALTER TABLE scratch_accounts
+	DROP COLUMN missing_checks,
+	DROP COLUMN gone_at;
@@ -0,0 +1,3 @@
+ALTER TABLE scratch_accounts
+	ADD COLUMN missing_checks SMALLINT NOT NULL DEFAULT 0,
+	ADD COLUMN gone_at TIMESTAMP WITH TIME ZONE;
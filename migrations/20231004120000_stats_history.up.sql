@@ -0,0 +1,8 @@
+CREATE TABLE stats_history (
+	username TEXT NOT NULL,
+	recorded_at TIMESTAMP WITH TIME ZONE NOT NULL,
+	followers BIGINT NOT NULL,
+	status SMALLINT
+);
+
+CREATE UNIQUE INDEX ON stats_history (lower(username), recorded_at);
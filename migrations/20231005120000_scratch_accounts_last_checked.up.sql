@@ -0,0 +1,2 @@
+ALTER TABLE scratch_accounts
+	ADD COLUMN last_checked_at TIMESTAMP WITH TIME ZONE;
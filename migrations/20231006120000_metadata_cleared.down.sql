@@ -0,0 +1,6 @@
+DELETE FROM metadata WHERE scratcher IS NULL;
+
+ALTER TABLE metadata
+	ALTER COLUMN scratcher SET NOT NULL,
+	ALTER COLUMN followers SET NOT NULL,
+	ALTER COLUMN joined SET NOT NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE metadata
+	ALTER COLUMN scratcher DROP NOT NULL,
+	ALTER COLUMN followers DROP NOT NULL,
+	ALTER COLUMN joined DROP NOT NULL;
@@ -0,0 +1,92 @@
+//! Everything shared by the Shuttle and standalone entry points.
+
+use anyhow::Context;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use tower_http::trace::TraceLayer;
+use tracing::{debug, error, info, trace, warn};
+use tracing_panic::panic_hook;
+
+use crate::{
+    backfill,
+    database::MIGRATOR,
+    health,
+    interactions::{interaction_handler, register::register_commands},
+    linked_roles::{self, register_metadata, spawn_background_updater},
+    metrics,
+    state::AppState,
+    verification::{spawn_cleanup, spawn_poller, spawn_studio_health_check},
+};
+
+async fn hello_world() -> &'static str {
+    "Hello, world!"
+}
+
+pub fn init_tracing() {
+    tracing_subscriber::fmt()
+        .with_env_filter("info,scratchy=trace,tower_http=trace")
+        .init();
+    debug!("tracing initialized");
+
+    trace!("trace");
+    debug!("debug");
+    info!("info");
+    warn!("warn");
+    error!("error");
+
+    debug!("setting panic hook");
+    std::panic::set_hook(Box::new(panic_hook));
+}
+
+/// Routes only, without any setup, so that it can be used in tests.
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/hello", get(hello_world))
+        .route("/interactions", post(interaction_handler))
+        .merge(linked_roles::router())
+        .merge(health::router())
+        .merge(metrics::router())
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
+
+/// Prepares the database and Discord, spawns the background tasks and returns the router.
+pub async fn build(state: AppState) -> anyhow::Result<Router> {
+    debug!("running migrations");
+    MIGRATOR
+        .run(&state.pool)
+        .await
+        .context("database migration failed")?;
+
+    debug!("registering commands");
+    register_commands(state.config.token.to_string(), state.config.dev_guild_id)
+        .await
+        .context("failed to register commands")?;
+
+    debug!("registering metadata");
+    register_metadata(&state)
+        .await
+        .context("failed to register metadata")?;
+
+    debug!("creating router");
+    let router = router(state.clone());
+
+    debug!("spawning background metadata updater");
+    spawn_background_updater(state.clone());
+
+    debug!("spawning pending verification cleanup");
+    spawn_cleanup(state.clone());
+
+    debug!("spawning verification poller");
+    spawn_poller(state.clone());
+
+    debug!("spawning verification studio health check");
+    spawn_studio_health_check(state.clone());
+
+    debug!("spawning Scratch ID backfill");
+    backfill::spawn(state);
+
+    Ok(router)
+}
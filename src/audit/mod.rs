@@ -0,0 +1,190 @@
+//! Linked and unlinked members of a server, for `/audit`.
+//!
+//! Listing members requires the Server Members privileged intent.
+
+use std::{collections::HashMap, fmt::Write};
+
+use twilight_model::{
+    guild::Member,
+    id::{
+        marker::{GuildMarker, UserMarker},
+        Id,
+    },
+};
+
+use crate::{database::Database, state::AppState};
+
+/// Maximum allowed by Discord.
+const MEMBERS_PER_REQUEST: u16 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditedMember {
+    pub id: Id<UserMarker>,
+    pub name: String,
+    pub usernames: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Audit {
+    /// Every member except bots, ordered by ID
+    pub members: Vec<AuditedMember>,
+}
+
+impl Audit {
+    pub fn linked(&self) -> usize {
+        self.members
+            .iter()
+            .filter(|member| !member.usernames.is_empty())
+            .count()
+    }
+
+    pub fn unlinked(&self) -> Vec<&AuditedMember> {
+        self.members
+            .iter()
+            .filter(|member| member.usernames.is_empty())
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("id,name,linked,scratch_accounts\n");
+
+        for member in &self.members {
+            writeln!(
+                csv,
+                "{},{},{},{}",
+                member.id,
+                escape(&member.name),
+                !member.usernames.is_empty(),
+                escape(&member.usernames.join(";")),
+            )
+            .unwrap();
+        }
+
+        csv
+    }
+}
+
+/// Quotes the field if it contains a separator, quote or line break.
+///
+/// Fields which spreadsheets would run as formulas are prefixed with `'`, since member names are
+/// chosen by the members.
+fn escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    };
+
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+pub async fn audit(state: &AppState, guild_id: Id<GuildMarker>) -> anyhow::Result<Audit> {
+    let members = list_members(state, guild_id).await?;
+
+    let ids: Vec<_> = members.iter().map(|member| member.user.id).collect();
+
+    let mut usernames: HashMap<_, Vec<_>> = HashMap::new();
+    for account in state.pool.get_linked_scratch_accounts_of(&ids).await? {
+        usernames
+            .entry(account.id)
+            .or_default()
+            .push(account.username);
+    }
+
+    let members = members
+        .into_iter()
+        .map(|member| AuditedMember {
+            id: member.user.id,
+            name: member.user.name,
+            usernames: usernames.remove(&member.user.id).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(Audit { members })
+}
+
+/// Every member of the server except bots, also used to sync roles.
+pub async fn list_members(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+) -> anyhow::Result<Vec<Member>> {
+    let mut members = Vec::new();
+    let mut after = None;
+
+    loop {
+        let mut request = state
+            .discord_client
+            .guild_members(guild_id)
+            .limit(MEMBERS_PER_REQUEST)?;
+
+        if let Some(after) = after {
+            request = request.after(after);
+        }
+
+        let page = request.await?.models().await?;
+        let last_page = page.len() < MEMBERS_PER_REQUEST as usize;
+
+        after = page.last().map(|member| member.user.id);
+        members.extend(page.into_iter().filter(|member| !member.user.bot));
+
+        if last_page {
+            break;
+        }
+    }
+
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audit() -> Audit {
+        Audit {
+            members: vec![
+                AuditedMember {
+                    id: "755497867606622450".parse().unwrap(),
+                    name: "pmj".into(),
+                    usernames: vec!["PMJ_Studio".into(), "PMJ_test".into()],
+                },
+                AuditedMember {
+                    id: "775316334259077120".parse().unwrap(),
+                    name: "comma, \"quote\"".into(),
+                    usernames: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn counts() {
+        let audit = audit();
+
+        assert_eq!(audit.linked(), 1);
+        assert_eq!(audit.unlinked(), vec![&audit.members[1]]);
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            audit().to_csv(),
+            "id,name,linked,scratch_accounts\n\
+            755497867606622450,pmj,true,PMJ_Studio;PMJ_test\n\
+            775316334259077120,\"comma, \"\"quote\"\"\",false,\n"
+        );
+    }
+
+    #[test]
+    fn csv_formulas() {
+        assert_eq!(escape("=1+1"), "'=1+1");
+        assert_eq!(escape("+1"), "'+1");
+        assert_eq!(escape("-1"), "'-1");
+        assert_eq!(escape("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape("=A1,B1"), "\"'=A1,B1\"");
+        assert_eq!(escape("a=1"), "a=1");
+    }
+}
@@ -0,0 +1,80 @@
+//! Fills in the Scratch IDs of accounts linked before they were stored.
+
+use std::time::Duration;
+
+use tokio::{
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, error, warn};
+
+use crate::{database::Database, scratch::api::ScratchAPIClient, state::AppState};
+
+/// Accounts looked up per tick, to stay well below the Scratch API rate limits.
+const BATCH_SIZE: i64 = 20;
+
+pub fn spawn(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(backfill(state))
+}
+
+async fn backfill(state: AppState) -> () {
+    debug!("starting Scratch ID backfill");
+
+    let mut minute = interval(Duration::from_secs(60));
+    minute.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    // Accounts which fail or aren't found stay without an ID, so they wait for the next pass
+    let mut after = String::new();
+
+    loop {
+        minute.tick().await;
+
+        match backfill_batch(&state, &after).await {
+            Ok(Some(last)) => after = last,
+            Ok(None) => {
+                if !after.is_empty() {
+                    debug!("finished a Scratch ID backfill pass");
+                    after.clear();
+                }
+                // Only accounts which failed before or weren't found are left
+                tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+            }
+            Err(err) => error!("{}", err),
+        }
+    }
+}
+
+/// Returns the last username of the batch, or `None` if there was nothing to backfill.
+async fn backfill_batch(state: &AppState, after: &str) -> anyhow::Result<Option<String>> {
+    let accounts = state
+        .pool
+        .get_scratch_accounts_without_scratch_id(after.to_string(), BATCH_SIZE)
+        .await?;
+
+    let Some(last) = accounts.last().map(|account| account.username.to_owned()) else {
+        return Ok(None);
+    };
+
+    for account in accounts {
+        // One failing account shouldn't stop the others from being backfilled
+        if let Err(err) = backfill_account(state, &account.username).await {
+            error!("failed to backfill {}: {}", account.username, err);
+        }
+    }
+
+    Ok(Some(last))
+}
+
+async fn backfill_account(state: &AppState, username: &str) -> anyhow::Result<()> {
+    match state.reqwest_client.get_scratch_api_user(username).await? {
+        Some(user) => {
+            state
+                .pool
+                .set_scratch_id(username.to_string(), user.id)
+                .await?;
+        }
+        None => warn!("linked Scratch account {} not found", username),
+    }
+
+    Ok(())
+}
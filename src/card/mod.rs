@@ -0,0 +1,251 @@
+//! PNG profile cards, attached to `/user` with `card:true`.
+//!
+//! Drawn on the CPU with tiny-skia, with the bundled DejaVu fonts so that the output doesn't
+//! depend on the fonts installed on the host.
+
+#[cfg(test)]
+mod tests;
+
+use ab_glyph::{Font, FontRef, GlyphId, OutlineCurve, Point, PxScaleFont, ScaleFont};
+use tiny_skia::{
+    Color, FillRule, FilterQuality, Paint, Path, PathBuilder, Pattern, Pixmap, Rect, SpreadMode,
+    Transform,
+};
+
+static REGULAR: &[u8] = include_bytes!("fonts/DejaVuSans.ttf");
+static BOLD: &[u8] = include_bytes!("fonts/DejaVuSans-Bold.ttf");
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 220;
+
+const AVATAR_SIZE: f32 = 90.0;
+const AVATAR_X: f32 = 40.0;
+const AVATAR_Y: f32 = 40.0;
+
+/// Left edge of the text, next to the avatar
+const TEXT_X: f32 = 160.0;
+const MARGIN: f32 = 30.0;
+
+/// Top ranks which fit in one line
+pub const MAX_RANKS: usize = 3;
+
+/// Everything drawn on the card, with the text already localized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub username: String,
+    /// Drawn as a circle, scaled to 90x90
+    pub avatar: Option<Pixmap>,
+    pub status: Option<String>,
+    pub followers: Option<String>,
+    pub joined: Option<String>,
+    /// Only the first [`MAX_RANKS`] are drawn
+    pub ranks: Vec<String>,
+}
+
+impl Card {
+    pub fn render(&self) -> Pixmap {
+        let regular = FontRef::try_from_slice(REGULAR).unwrap();
+        let bold = FontRef::try_from_slice(BOLD).unwrap();
+
+        let mut pixmap = Pixmap::new(WIDTH, HEIGHT).unwrap();
+        pixmap.fill(color(0x2b2d31));
+
+        // Same as the embed color of `/user`
+        let accent = Rect::from_xywh(0.0, 0.0, 8.0, HEIGHT as f32).unwrap();
+        pixmap.fill_rect(accent, &solid(color(0xcc6600)), Transform::identity(), None);
+
+        draw_avatar(&mut pixmap, self.avatar.as_ref());
+
+        let max_width = WIDTH as f32 - TEXT_X - MARGIN;
+        let primary = color(0xf2f3f5);
+        let secondary = color(0xb5bac1);
+
+        let mut text = Text {
+            pixmap: &mut pixmap,
+            max_width,
+        };
+
+        text.draw(&bold.as_scaled(30.0), 68.0, primary, &self.username);
+
+        let lines = [&self.status, &self.followers, &self.joined];
+        for (line, y) in lines.into_iter().flatten().zip([100.0, 128.0, 156.0]) {
+            text.draw(&regular.as_scaled(18.0), y, secondary, line);
+        }
+
+        if !self.ranks.is_empty() {
+            let ranks = self.ranks[..self.ranks.len().min(MAX_RANKS)].join("   ");
+            text.draw(&bold.as_scaled(16.0), 192.0, primary, &ranks);
+        }
+
+        pixmap
+    }
+
+    pub fn to_png(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(self.render().encode_png()?)
+    }
+}
+
+/// `None` if the image isn't a PNG, the avatar is then left out.
+pub fn decode_avatar(bytes: &[u8]) -> Option<Pixmap> {
+    Pixmap::decode_png(bytes).ok()
+}
+
+fn color(rgb: u32) -> Color {
+    Color::from_rgba8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+}
+
+fn solid(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+    paint
+}
+
+fn draw_avatar(pixmap: &mut Pixmap, avatar: Option<&Pixmap>) {
+    let radius = AVATAR_SIZE / 2.0;
+    let circle = PathBuilder::from_circle(AVATAR_X + radius, AVATAR_Y + radius, radius).unwrap();
+
+    let paint = match avatar {
+        Some(avatar) => {
+            let scale_x = AVATAR_SIZE / avatar.width() as f32;
+            let scale_y = AVATAR_SIZE / avatar.height() as f32;
+
+            Paint {
+                shader: Pattern::new(
+                    avatar.as_ref(),
+                    SpreadMode::Pad,
+                    FilterQuality::Bicubic,
+                    1.0,
+                    Transform::from_row(scale_x, 0.0, 0.0, scale_y, AVATAR_X, AVATAR_Y),
+                ),
+                anti_alias: true,
+                ..Default::default()
+            }
+        }
+        None => solid(color(0x4e5058)),
+    };
+
+    pixmap.fill_path(
+        &circle,
+        &paint,
+        FillRule::Winding,
+        Transform::identity(),
+        None,
+    );
+}
+
+struct Text<'a> {
+    pixmap: &'a mut Pixmap,
+    max_width: f32,
+}
+
+impl Text<'_> {
+    /// Draws a line starting at [`TEXT_X`], cut off with an ellipsis if it's too wide.
+    fn draw(&mut self, font: &PxScaleFont<&FontRef>, baseline: f32, color: Color, text: &str) {
+        let paint = solid(color);
+
+        for (id, x) in layout(font, text, self.max_width) {
+            if let Some(path) = glyph_path(font, id, x, baseline) {
+                self.pixmap.fill_path(
+                    &path,
+                    &paint,
+                    FillRule::Winding,
+                    Transform::identity(),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+/// Positions of the glyphs which fit in `max_width`.
+fn layout(font: &PxScaleFont<&FontRef>, text: &str, max_width: f32) -> Vec<(GlyphId, f32)> {
+    let position = |text: &mut dyn Iterator<Item = char>| {
+        let mut glyphs = Vec::new();
+        let mut x = TEXT_X;
+        let mut previous = None;
+
+        for c in text {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += font.kern(previous, id);
+            }
+            glyphs.push((id, x));
+            x += font.h_advance(id);
+            previous = Some(id);
+        }
+
+        (glyphs, x - TEXT_X)
+    };
+
+    let (glyphs, width) = position(&mut text.chars());
+    if width <= max_width {
+        return glyphs;
+    }
+
+    // Drop characters until the rest fits together with the ellipsis
+    let chars: Vec<_> = text.chars().collect();
+    for end in (0..chars.len()).rev() {
+        let (glyphs, width) = position(&mut chars[..end].iter().copied().chain(['…']));
+        if width <= max_width {
+            return glyphs;
+        }
+    }
+
+    Vec::new()
+}
+
+/// Converts the outline of the glyph to a path, the font is in font units with y going up.
+fn glyph_path(font: &PxScaleFont<&FontRef>, id: GlyphId, x: f32, baseline: f32) -> Option<Path> {
+    let outline = font.font().outline(id)?;
+    let scale = font.scale_factor();
+    let point = |point: Point| {
+        (
+            x + point.x * scale.horizontal,
+            baseline - point.y * scale.vertical,
+        )
+    };
+
+    let mut builder = PathBuilder::new();
+    let mut last = None;
+
+    for curve in outline.curves {
+        let (start, end) = match curve {
+            OutlineCurve::Line(start, end)
+            | OutlineCurve::Quad(start, _, end)
+            | OutlineCurve::Cubic(start, _, _, end) => (start, end),
+        };
+
+        // Contours aren't marked, a new one starts wherever the previous curve didn't end
+        if last != Some(start) {
+            if last.is_some() {
+                builder.close();
+            }
+            let (x, y) = point(start);
+            builder.move_to(x, y);
+        }
+
+        match curve {
+            OutlineCurve::Line(_, end) => {
+                let (x, y) = point(end);
+                builder.line_to(x, y);
+            }
+            OutlineCurve::Quad(_, control, end) => {
+                let (x1, y1) = point(control);
+                let (x, y) = point(end);
+                builder.quad_to(x1, y1, x, y);
+            }
+            OutlineCurve::Cubic(_, control1, control2, end) => {
+                let (x1, y1) = point(control1);
+                let (x2, y2) = point(control2);
+                let (x, y) = point(end);
+                builder.cubic_to(x1, y1, x2, y2, x, y);
+            }
+        }
+
+        last = Some(end);
+    }
+
+    builder.close();
+    builder.finish()
+}
@@ -0,0 +1,107 @@
+//! Golden-image tests, rendered cards are compared to the PNGs next to this file.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the images again after an intended change.
+
+use std::path::PathBuf;
+
+use super::*;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/card/tests")
+        .join(format!("{name}.png"))
+}
+
+/// Allows tiny differences in anti-aliasing, like from a different SIMD implementation.
+fn assert_golden(name: &str, actual: &Pixmap) {
+    let path = golden_path(name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save_png(&path).unwrap();
+        return;
+    }
+
+    let expected = Pixmap::load_png(&path).unwrap();
+
+    assert_eq!(
+        (actual.width(), actual.height()),
+        (expected.width(), expected.height()),
+        "size of {name}",
+    );
+
+    let different = actual
+        .data()
+        .chunks(4)
+        .zip(expected.data().chunks(4))
+        .filter(|(actual, expected)| {
+            actual
+                .iter()
+                .zip(expected.iter())
+                .any(|(a, b)| a.abs_diff(*b) > 2)
+        })
+        .count();
+
+    assert_eq!(different, 0, "{different} pixels of {name} are different");
+}
+
+fn avatar() -> Pixmap {
+    decode_avatar(&std::fs::read(golden_path("avatar")).unwrap()).unwrap()
+}
+
+fn full() -> Card {
+    Card {
+        username: "PMJ_Studio".into(),
+        avatar: Some(avatar()),
+        status: Some("Scratcher".into()),
+        followers: Some("Followers: 1000".into()),
+        joined: Some("Joined: 2020-08-03".into()),
+        ranks: vec![
+            "Followers: #1234".into(),
+            "Loves: #5678".into(),
+            "Views: #9012".into(),
+            "Comments: #34567".into(),
+        ],
+    }
+}
+
+#[test]
+fn full_card() {
+    assert_golden("full", &full().render());
+}
+
+#[test]
+fn minimal_card() {
+    let card = Card {
+        username: "PMJ_test".into(),
+        avatar: None,
+        status: None,
+        followers: None,
+        joined: None,
+        ranks: Vec::new(),
+    };
+
+    assert_golden("minimal", &card.render());
+}
+
+#[test]
+fn long_text_cut_off() {
+    let card = Card {
+        username: "WWWWWWWWWWWWWWWWWWWW".into(),
+        status: Some("Nowy Scratcher, uczeń w szkole 123456".into()),
+        ..full()
+    };
+
+    assert_golden("long_text", &card.render());
+}
+
+#[test]
+fn png() {
+    let png = full().to_png().unwrap();
+
+    assert_eq!(decode_avatar(&png), Some(full().render()));
+}
+
+#[test]
+fn not_png() {
+    assert_eq!(decode_avatar(b"GIF89a"), None);
+}
@@ -0,0 +1,715 @@
+//! In-memory [`Database`] for tests which shouldn't need Postgres.
+//!
+//! It follows the same constraints as the migrations: primary keys, foreign keys to
+//! `discord_accounts` and the unique `lower(username)` indexes. Violations are returned as
+//! [`sqlx::Error::Database`] with the same [`ErrorKind`], and missing rows where Postgres would
+//! return none from `fetch_one` as [`sqlx::Error::RowNotFound`].
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    error::Error as StdError,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use sqlx::error::{DatabaseError, ErrorKind};
+use time::OffsetDateTime;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use twilight_model::id::{
+    marker::{GuildMarker, RoleMarker, UserMarker},
+    Id,
+};
+
+use super::{
+    Database, DatabaseTransaction, DiscordAccount, GuildConfig, PendingVerification, RoleRule,
+    ScratchAccount, StatsSnapshot, Transactional, MIGRATOR,
+};
+use crate::{
+    linked_roles::{RoleConnectionData, Token},
+    verification::VerifiedVia,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryDatabase(Arc<Mutex<Tables>>);
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Tables {
+    pub discord_accounts: BTreeSet<Id<UserMarker>>,
+    pub scratch_accounts: Vec<ScratchAccount>,
+    pub tokens: HashMap<Id<UserMarker>, Token>,
+    pub metadata: HashMap<Id<UserMarker>, (Option<RoleConnectionData>, OffsetDateTime)>,
+    pub stats_history: Vec<StatsSnapshot>,
+    pub pending_verifications: Vec<PendingVerification>,
+    pub role_rules: Vec<RoleRule>,
+    pub guild_config: HashMap<Id<GuildMarker>, GuildConfig>,
+}
+
+impl MemoryDatabase {
+    /// Direct access to the rows, for inserting fixtures.
+    #[cfg(test)]
+    pub(super) async fn tables(&self) -> tokio::sync::MutexGuard<'_, Tables> {
+        self.0.lock().await
+    }
+}
+
+impl Tables {
+    fn check_discord_account(
+        &self,
+        id: Id<UserMarker>,
+        table: &'static str,
+    ) -> Result<(), sqlx::Error> {
+        if self.discord_accounts.contains(&id) {
+            Ok(())
+        } else {
+            Err(ConstraintError::foreign_key(table))
+        }
+    }
+}
+
+/// Holds the lock for the whole transaction, so transactions run one at a time.
+/// Changes are made on a copy and only kept if committed.
+#[derive(Debug)]
+pub struct MemoryTransaction {
+    tables: OwnedMutexGuard<Tables>,
+    copy: MemoryDatabase,
+}
+
+#[async_trait]
+impl Transactional for MemoryDatabase {
+    type Transaction = MemoryTransaction;
+
+    async fn transaction(&self) -> Result<Self::Transaction, sqlx::Error> {
+        let tables = self.0.clone().lock_owned().await;
+        let copy = MemoryDatabase(Arc::new(Mutex::new(tables.clone())));
+
+        Ok(MemoryTransaction { tables, copy })
+    }
+}
+
+#[async_trait]
+impl DatabaseTransaction for MemoryTransaction {
+    type Executor<'e> = &'e MemoryDatabase;
+
+    fn executor(&mut self) -> Self::Executor<'_> {
+        &self.copy
+    }
+
+    async fn commit(mut self) -> Result<(), sqlx::Error> {
+        *self.tables = self.copy.0.lock().await.clone();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Constraint {
+    Unique,
+    ForeignKey,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+struct ConstraintError {
+    message: String,
+    constraint: Constraint,
+    table: &'static str,
+}
+
+impl ConstraintError {
+    fn unique(table: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(Self {
+            message: format!("duplicate key value violates unique constraint on \"{table}\""),
+            constraint: Constraint::Unique,
+            table,
+        }))
+    }
+
+    fn foreign_key(table: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(Self {
+            message: format!(
+                "insert or update on table \"{table}\" violates foreign key constraint"
+            ),
+            constraint: Constraint::ForeignKey,
+            table,
+        }))
+    }
+}
+
+impl DatabaseError for ConstraintError {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        // Postgres error codes, so checks of the code work the same
+        match self.constraint {
+            Constraint::Unique => Some("23505".into()),
+            Constraint::ForeignKey => Some("23503".into()),
+        }
+    }
+
+    fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+        self
+    }
+
+    fn table(&self) -> Option<&str> {
+        Some(self.table)
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self.constraint {
+            Constraint::Unique => ErrorKind::UniqueViolation,
+            Constraint::ForeignKey => ErrorKind::ForeignKeyViolation,
+        }
+    }
+}
+
+fn same_username(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+#[async_trait]
+impl Database for &MemoryDatabase {
+    type Error = sqlx::Error;
+
+    async fn get_discord_account(
+        self,
+        id: Id<UserMarker>,
+    ) -> Result<Option<DiscordAccount>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        Ok(tables
+            .discord_accounts
+            .contains(&id)
+            .then_some(DiscordAccount { id }))
+    }
+
+    async fn create_discord_account(
+        self,
+        id: Id<UserMarker>,
+    ) -> Result<DiscordAccount, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        if !tables.discord_accounts.insert(id) {
+            return Err(ConstraintError::unique("discord_accounts"));
+        }
+
+        Ok(DiscordAccount { id })
+    }
+
+    async fn get_scratch_account(
+        self,
+        username: String,
+        scratch_id: Option<i64>,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let by_id = tables
+            .scratch_accounts
+            .iter()
+            .find(|account| scratch_id.is_some() && account.scratch_id == scratch_id);
+
+        let by_username = || {
+            tables.scratch_accounts.iter().find(|account| {
+                same_username(&account.username, &username)
+                    && (account.scratch_id.is_none() || scratch_id.is_none())
+            })
+        };
+
+        Ok(by_id.or_else(by_username).cloned())
+    }
+
+    async fn get_linked_scratch_accounts(
+        self,
+        id: Id<UserMarker>,
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut accounts: Vec<_> = tables
+            .scratch_accounts
+            .iter()
+            .filter(|account| account.id == id)
+            .cloned()
+            .collect();
+
+        // Accounts without a link date go last, like NULLs in Postgres
+        accounts.sort_by(|a, b| {
+            (a.linked_at.is_none(), a.linked_at, &a.username).cmp(&(
+                b.linked_at.is_none(),
+                b.linked_at,
+                &b.username,
+            ))
+        });
+
+        Ok(accounts)
+    }
+
+    async fn get_linked_scratch_accounts_of(
+        self,
+        ids: &[Id<UserMarker>],
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut accounts: Vec<_> = tables
+            .scratch_accounts
+            .iter()
+            .filter(|account| ids.contains(&account.id))
+            .cloned()
+            .collect();
+
+        // IDs are stored as text, so they are ordered as strings
+        accounts
+            .sort_by(|a, b| (a.id.to_string(), &a.username).cmp(&(b.id.to_string(), &b.username)));
+
+        Ok(accounts)
+    }
+
+    async fn create_linked_scratch_account(
+        self,
+        account: &ScratchAccount,
+    ) -> Result<ScratchAccount, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        if tables.scratch_accounts.iter().any(|other| {
+            same_username(&other.username, &account.username)
+                || (account.scratch_id.is_some() && other.scratch_id == account.scratch_id)
+        }) {
+            return Err(ConstraintError::unique("scratch_accounts"));
+        }
+        tables.check_discord_account(account.id, "scratch_accounts")?;
+
+        tables.scratch_accounts.push(account.clone());
+
+        Ok(account.clone())
+    }
+
+    async fn delete_stale_scratch_account(
+        self,
+        username: String,
+        scratch_id: i64,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        let index = tables.scratch_accounts.iter().position(|account| {
+            same_username(&account.username, &username)
+                && account.scratch_id.is_some_and(|id| id != scratch_id)
+        });
+
+        Ok(index.map(|index| tables.scratch_accounts.remove(index)))
+    }
+
+    async fn get_scratch_accounts_without_scratch_id(
+        self,
+        after: String,
+        limit: i64,
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut accounts: Vec<_> = tables
+            .scratch_accounts
+            .iter()
+            .filter(|account| account.scratch_id.is_none() && account.username > after)
+            .cloned()
+            .collect();
+        accounts.sort_by(|a, b| a.username.cmp(&b.username));
+        accounts.truncate(limit as usize);
+
+        Ok(accounts)
+    }
+
+    async fn set_scratch_id(
+        self,
+        username: String,
+        scratch_id: i64,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        if tables.scratch_accounts.iter().any(|account| {
+            account.scratch_id == Some(scratch_id) && !same_username(&account.username, &username)
+        }) {
+            return Err(ConstraintError::unique("scratch_accounts"));
+        }
+
+        Ok(tables
+            .scratch_accounts
+            .iter_mut()
+            .find(|account| same_username(&account.username, &username))
+            .map(|account| {
+                account.scratch_id = Some(scratch_id);
+                account.clone()
+            }))
+    }
+
+    async fn get_scratch_accounts_to_check(
+        self,
+        before: OffsetDateTime,
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut accounts: Vec<_> = tables
+            .scratch_accounts
+            .iter()
+            .filter(|account| {
+                account
+                    .last_checked_at
+                    .map_or(true, |checked_at| checked_at < before)
+            })
+            .cloned()
+            .collect();
+
+        // Never checked accounts go first, like NULLs with `NULLS FIRST`
+        accounts.sort_by(|a, b| {
+            (a.last_checked_at.is_some(), a.last_checked_at, &a.username).cmp(&(
+                b.last_checked_at.is_some(),
+                b.last_checked_at,
+                &b.username,
+            ))
+        });
+
+        Ok(accounts)
+    }
+
+    async fn record_scratch_account_check(
+        self,
+        username: String,
+        found: bool,
+        threshold: i16,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        Ok(tables
+            .scratch_accounts
+            .iter_mut()
+            .find(|account| same_username(&account.username, &username))
+            .map(|account| {
+                if found {
+                    account.missing_checks = 0;
+                    account.gone_at = None;
+                } else {
+                    account.missing_checks = (account.missing_checks + 1).min(threshold);
+                    if account.missing_checks >= threshold && account.gone_at.is_none() {
+                        account.gone_at = Some(OffsetDateTime::now_utc());
+                    }
+                }
+                account.last_checked_at = Some(OffsetDateTime::now_utc());
+                account.clone()
+            }))
+    }
+
+    async fn transfer_linked_scratch_accounts(
+        self,
+        from: Id<UserMarker>,
+        to: Id<UserMarker>,
+    ) -> Result<Vec<String>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        let linked = tables
+            .scratch_accounts
+            .iter()
+            .any(|account| account.id == from);
+        if linked {
+            tables.check_discord_account(to, "scratch_accounts")?;
+        }
+
+        let now = OffsetDateTime::now_utc();
+
+        Ok(tables
+            .scratch_accounts
+            .iter_mut()
+            .filter(|account| account.id == from)
+            .map(|account| {
+                account.id = to;
+                account.linked_at = Some(now);
+                account.verified_via = Some(VerifiedVia::Transfer);
+                account.username.to_owned()
+            })
+            .collect())
+    }
+
+    async fn get_token(self, id: Id<UserMarker>) -> Result<Option<Token>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        Ok(tables.tokens.get(&id).cloned())
+    }
+
+    async fn write_token(self, id: Id<UserMarker>, token: Token) -> Result<Token, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        tables.check_discord_account(id, "tokens")?;
+        tables.tokens.insert(id, token.clone());
+
+        Ok(token)
+    }
+
+    async fn delete_token(self, id: Id<UserMarker>) -> Result<Token, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        tables.tokens.remove(&id).ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn get_oldest_metadata(
+        self,
+    ) -> Result<Option<(Id<UserMarker>, OffsetDateTime)>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        Ok(tables
+            .metadata
+            .iter()
+            .map(|(id, (_, updated_at))| (*id, *updated_at))
+            .min_by_key(|(_, updated_at)| *updated_at))
+    }
+
+    async fn get_metadata(
+        self,
+        id: Id<UserMarker>,
+    ) -> Result<Option<RoleConnectionData>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        Ok(tables.metadata.get(&id).and_then(|(data, _)| data.clone()))
+    }
+
+    async fn write_metadata(
+        self,
+        id: Id<UserMarker>,
+        data: Option<&RoleConnectionData>,
+    ) -> Result<Option<RoleConnectionData>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        // Only updates existing rows, like the query
+        let row = tables
+            .metadata
+            .get_mut(&id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        *row = (data.cloned(), OffsetDateTime::now_utc());
+
+        Ok(data.cloned())
+    }
+
+    async fn write_stats_snapshot(
+        self,
+        snapshot: &StatsSnapshot,
+    ) -> Result<StatsSnapshot, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        if tables.stats_history.iter().any(|other| {
+            same_username(&other.username, &snapshot.username)
+                && other.recorded_at == snapshot.recorded_at
+        }) {
+            return Err(ConstraintError::unique("stats_history"));
+        }
+
+        tables.stats_history.push(snapshot.clone());
+
+        Ok(snapshot.clone())
+    }
+
+    async fn get_stats_history(
+        self,
+        username: String,
+        limit: i64,
+    ) -> Result<Vec<StatsSnapshot>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut history: Vec<_> = tables
+            .stats_history
+            .iter()
+            .filter(|snapshot| same_username(&snapshot.username, &username))
+            .cloned()
+            .collect();
+        history.sort_by_key(|snapshot| snapshot.recorded_at);
+
+        let skipped = history.len().saturating_sub(limit.try_into().unwrap_or(0));
+
+        Ok(history.split_off(skipped))
+    }
+
+    async fn delete_stats_snapshots_before(
+        self,
+        before: OffsetDateTime,
+    ) -> Result<u64, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        let count = tables.stats_history.len();
+        tables
+            .stats_history
+            .retain(|snapshot| snapshot.recorded_at >= before);
+
+        Ok((count - tables.stats_history.len()) as u64)
+    }
+
+    async fn get_pending_verification(
+        self,
+        id: Id<UserMarker>,
+        username: String,
+    ) -> Result<Option<PendingVerification>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        Ok(tables
+            .pending_verifications
+            .iter()
+            .find(|verification| {
+                verification.id == id && same_username(&verification.username, &username)
+            })
+            .cloned())
+    }
+
+    async fn get_active_pending_verifications(
+        self,
+    ) -> Result<Vec<PendingVerification>, Self::Error> {
+        let tables = self.0.lock().await;
+        let now = OffsetDateTime::now_utc();
+
+        Ok(tables
+            .pending_verifications
+            .iter()
+            .filter(|verification| verification.expires_at >= now)
+            .cloned()
+            .collect())
+    }
+
+    async fn write_pending_verification(
+        self,
+        verification: &PendingVerification,
+    ) -> Result<PendingVerification, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        tables
+            .pending_verifications
+            .retain(|other| other.id != verification.id);
+        tables.pending_verifications.push(verification.clone());
+
+        Ok(verification.clone())
+    }
+
+    async fn delete_pending_verification(
+        self,
+        id: Id<UserMarker>,
+        username: String,
+    ) -> Result<Option<PendingVerification>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        let index = tables
+            .pending_verifications
+            .iter()
+            .position(|verification| {
+                verification.id == id && same_username(&verification.username, &username)
+            });
+
+        Ok(index.map(|index| tables.pending_verifications.remove(index)))
+    }
+
+    async fn delete_expired_pending_verifications(self) -> Result<u64, Self::Error> {
+        let mut tables = self.0.lock().await;
+        let now = OffsetDateTime::now_utc();
+
+        let before = tables.pending_verifications.len();
+        tables
+            .pending_verifications
+            .retain(|verification| verification.expires_at >= now);
+
+        Ok((before - tables.pending_verifications.len()) as u64)
+    }
+
+    async fn get_role_rules(self) -> Result<Vec<RoleRule>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut rules = tables.role_rules.clone();
+        // IDs are stored as text, so they are ordered as strings
+        rules.sort_by_key(|rule| (rule.guild_id.to_string(), rule.role_id.to_string()));
+
+        Ok(rules)
+    }
+
+    async fn get_guild_role_rules(
+        self,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<Vec<RoleRule>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut rules: Vec<_> = tables
+            .role_rules
+            .iter()
+            .filter(|rule| rule.guild_id == guild_id)
+            .cloned()
+            .collect();
+        rules.sort_by_key(|rule| rule.role_id.to_string());
+
+        Ok(rules)
+    }
+
+    async fn write_role_rule(self, rule: &RoleRule) -> Result<RoleRule, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        tables
+            .role_rules
+            .retain(|other| !(other.guild_id == rule.guild_id && other.role_id == rule.role_id));
+        tables.role_rules.push(rule.clone());
+
+        Ok(rule.clone())
+    }
+
+    async fn delete_role_rule(
+        self,
+        guild_id: Id<GuildMarker>,
+        role_id: Id<RoleMarker>,
+    ) -> Result<Option<RoleRule>, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        let index = tables
+            .role_rules
+            .iter()
+            .position(|rule| rule.guild_id == guild_id && rule.role_id == role_id);
+
+        Ok(index.map(|index| tables.role_rules.remove(index)))
+    }
+
+    async fn get_guild_config(
+        self,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<Option<GuildConfig>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        Ok(tables.guild_config.get(&guild_id).cloned())
+    }
+
+    async fn write_guild_config(self, config: &GuildConfig) -> Result<GuildConfig, Self::Error> {
+        let mut tables = self.0.lock().await;
+
+        tables.guild_config.insert(config.guild_id, config.clone());
+
+        Ok(config.clone())
+    }
+
+    async fn get_guilds_with_log_channel(self) -> Result<Vec<Id<GuildMarker>>, Self::Error> {
+        let tables = self.0.lock().await;
+
+        let mut guild_ids: Vec<_> = tables
+            .guild_config
+            .values()
+            .filter(|config| config.log_channel_id.is_some())
+            .map(|config| config.guild_id)
+            .collect();
+        guild_ids.sort_by_key(|id| id.to_string());
+
+        Ok(guild_ids)
+    }
+
+    /// Every migration, the tables always match the latest schema.
+    async fn get_applied_migrations(self) -> Result<Vec<i64>, Self::Error> {
+        let versions: BTreeSet<_> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .collect();
+
+        Ok(versions.into_iter().collect())
+    }
+}
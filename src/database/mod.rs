@@ -1,12 +1,27 @@
+#[cfg(any(test, feature = "memory-db"))]
+mod memory;
 #[cfg(test)]
 mod tests;
 
 use async_trait::async_trait;
-use sqlx::{Executor, PgPool, Postgres};
+use sqlx::{migrate::Migrator, Executor, PgConnection, PgPool, Postgres};
 use time::OffsetDateTime;
-use twilight_model::id::{marker::UserMarker, Id};
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, RoleMarker, UserMarker},
+    Id,
+};
 
-use crate::linked_roles::{RoleConnectionData, Token};
+use crate::{
+    linked_roles::{RoleConnectionData, Token},
+    roles::Condition,
+    scratch::db::user::Status,
+    verification::{Method, VerifiedVia},
+};
+
+#[cfg(any(test, feature = "memory-db"))]
+pub use memory::{MemoryDatabase, MemoryTransaction};
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DiscordAccount {
@@ -17,6 +32,83 @@ pub struct DiscordAccount {
 pub struct ScratchAccount {
     pub username: String,
     pub id: Id<UserMarker>,
+    /// ID of the Scratch user, `None` until backfilled for accounts linked before it was stored
+    pub scratch_id: Option<i64>,
+    /// `None` for accounts linked before it was stored, like `verified_via`
+    pub linked_at: Option<OffsetDateTime>,
+    pub verified_via: Option<VerifiedVia>,
+    /// Background checks in a row which didn't find the account on Scratch
+    pub missing_checks: i16,
+    /// Set once the account was missing for enough checks, it's then left out of the metadata
+    pub gone_at: Option<OffsetDateTime>,
+    /// Of the last check which got an answer from Scratch
+    pub last_checked_at: Option<OffsetDateTime>,
+}
+
+/// Stats of a Scratch account at one daily check, shown by `/stats history`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    pub username: String,
+    pub recorded_at: OffsetDateTime,
+    pub followers: i64,
+    pub status: Option<Status>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingVerification {
+    pub id: Id<UserMarker>,
+    pub username: String,
+    pub code: String,
+    pub method: Method,
+    pub generated_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    /// Token of the interaction which showed the code, used to edit its message
+    pub interaction_token: Option<String>,
+    pub locale: Option<String>,
+    /// Studio assigned to comment verifications
+    pub studio_id: Option<i64>,
+    /// Server where the verification was started, for event logs
+    pub guild_id: Option<Id<GuildMarker>>,
+}
+
+/// Role given by the bot to members matching the condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleRule {
+    pub guild_id: Id<GuildMarker>,
+    pub role_id: Id<RoleMarker>,
+    pub condition: Condition,
+}
+
+/// Per-server settings, with defaults for servers which never changed them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuildConfig {
+    pub guild_id: Id<GuildMarker>,
+    /// Where events like new links are posted
+    pub log_channel_id: Option<Id<ChannelMarker>>,
+    /// Whether command responses are only visible to the user by default
+    pub ephemeral: bool,
+    /// Used instead of the user's locale
+    pub locale: Option<String>,
+    pub disabled_commands: Vec<String>,
+    /// Whether nicknames are set to Scratch usernames
+    pub sync_nicknames: bool,
+}
+
+impl GuildConfig {
+    pub fn new(guild_id: Id<GuildMarker>) -> Self {
+        Self {
+            guild_id,
+            log_channel_id: None,
+            ephemeral: false,
+            locale: None,
+            disabled_commands: Vec::new(),
+            sync_nicknames: false,
+        }
+    }
+
+    pub fn is_enabled(&self, command: &str) -> bool {
+        !self.disabled_commands.iter().any(|name| name == command)
+    }
 }
 
 #[async_trait]
@@ -33,22 +125,67 @@ pub trait Database {
         id: Id<UserMarker>,
     ) -> Result<DiscordAccount, Self::Error>;
 
+    /// Finds the account by Scratch ID if given, otherwise by username. An account with the same
+    /// username but another Scratch ID was deleted and isn't returned.
     async fn get_scratch_account(
         self,
         username: String,
+        scratch_id: Option<i64>,
     ) -> Result<Option<ScratchAccount>, Self::Error>;
 
+    /// Ordered by when they were linked, so the first one is the primary account.
     async fn get_linked_scratch_accounts(
         self,
         id: Id<UserMarker>,
     ) -> Result<Vec<ScratchAccount>, Self::Error>;
 
+    /// Linked accounts of any of the users, for checking many members at once.
+    async fn get_linked_scratch_accounts_of(
+        self,
+        ids: &[Id<UserMarker>],
+    ) -> Result<Vec<ScratchAccount>, Self::Error>;
+
     async fn create_linked_scratch_account(
         self,
-        username: String,
-        id: Id<UserMarker>,
+        account: &ScratchAccount,
     ) -> Result<ScratchAccount, Self::Error>;
 
+    /// Removes the link of a deleted account whose username now belongs to another Scratch ID.
+    async fn delete_stale_scratch_account(
+        self,
+        username: String,
+        scratch_id: i64,
+    ) -> Result<Option<ScratchAccount>, Self::Error>;
+
+    /// Accounts without a Scratch ID, ordered by username and starting after `after`.
+    async fn get_scratch_accounts_without_scratch_id(
+        self,
+        after: String,
+        limit: i64,
+    ) -> Result<Vec<ScratchAccount>, Self::Error>;
+
+    async fn set_scratch_id(
+        self,
+        username: String,
+        scratch_id: i64,
+    ) -> Result<Option<ScratchAccount>, Self::Error>;
+
+    /// Accounts which weren't checked since `before`, the ones checked longest ago first.
+    async fn get_scratch_accounts_to_check(
+        self,
+        before: OffsetDateTime,
+    ) -> Result<Vec<ScratchAccount>, Self::Error>;
+
+    /// Counts a background check of the account. It's marked as gone after `threshold` checks in
+    /// a row which didn't find it, and finding it again clears both.
+    async fn record_scratch_account_check(
+        self,
+        username: String,
+        found: bool,
+        threshold: i16,
+    ) -> Result<Option<ScratchAccount>, Self::Error>;
+
+    /// Also marks the accounts as linked now via [`VerifiedVia::Transfer`].
     async fn transfer_linked_scratch_accounts(
         self,
         from: Id<UserMarker>,
@@ -65,16 +202,90 @@ pub trait Database {
         self,
     ) -> Result<Option<(Id<UserMarker>, OffsetDateTime)>, Self::Error>;
 
+    /// `None` if it isn't stored or was cleared.
     async fn get_metadata(
         self,
         id: Id<UserMarker>,
     ) -> Result<Option<RoleConnectionData>, Self::Error>;
 
+    /// `None` clears the values once none of the linked accounts count.
     async fn write_metadata(
         self,
         id: Id<UserMarker>,
-        data: &RoleConnectionData,
-    ) -> Result<RoleConnectionData, Self::Error>;
+        data: Option<&RoleConnectionData>,
+    ) -> Result<Option<RoleConnectionData>, Self::Error>;
+
+    async fn write_stats_snapshot(
+        self,
+        snapshot: &StatsSnapshot,
+    ) -> Result<StatsSnapshot, Self::Error>;
+
+    /// The latest `limit` snapshots of the account, oldest first.
+    async fn get_stats_history(
+        self,
+        username: String,
+        limit: i64,
+    ) -> Result<Vec<StatsSnapshot>, Self::Error>;
+
+    /// Removes the snapshots recorded before `before`, returning how many.
+    async fn delete_stats_snapshots_before(
+        self,
+        before: OffsetDateTime,
+    ) -> Result<u64, Self::Error>;
+
+    async fn get_pending_verification(
+        self,
+        id: Id<UserMarker>,
+        username: String,
+    ) -> Result<Option<PendingVerification>, Self::Error>;
+
+    async fn get_active_pending_verifications(
+        self,
+    ) -> Result<Vec<PendingVerification>, Self::Error>;
+
+    /// Replaces any previous pending verification of the Discord user, so that only the latest
+    /// code is valid.
+    async fn write_pending_verification(
+        self,
+        verification: &PendingVerification,
+    ) -> Result<PendingVerification, Self::Error>;
+
+    async fn delete_pending_verification(
+        self,
+        id: Id<UserMarker>,
+        username: String,
+    ) -> Result<Option<PendingVerification>, Self::Error>;
+
+    async fn delete_expired_pending_verifications(self) -> Result<u64, Self::Error>;
+
+    /// Rules of every server.
+    async fn get_role_rules(self) -> Result<Vec<RoleRule>, Self::Error>;
+
+    async fn get_guild_role_rules(
+        self,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<Vec<RoleRule>, Self::Error>;
+
+    /// Replaces the previous rule of the same role.
+    async fn write_role_rule(self, rule: &RoleRule) -> Result<RoleRule, Self::Error>;
+
+    async fn delete_role_rule(
+        self,
+        guild_id: Id<GuildMarker>,
+        role_id: Id<RoleMarker>,
+    ) -> Result<Option<RoleRule>, Self::Error>;
+
+    async fn get_guild_config(
+        self,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<Option<GuildConfig>, Self::Error>;
+
+    async fn write_guild_config(self, config: &GuildConfig) -> Result<GuildConfig, Self::Error>;
+
+    async fn get_guilds_with_log_channel(self) -> Result<Vec<Id<GuildMarker>>, Self::Error>;
+
+    /// Versions of the successfully applied migrations, in order.
+    async fn get_applied_migrations(self) -> Result<Vec<i64>, Self::Error>;
 }
 
 // Not sure how this works, but it works
@@ -127,18 +338,29 @@ where
     async fn get_scratch_account(
         self,
         username: String,
+        scratch_id: Option<i64>,
     ) -> Result<Option<ScratchAccount>, Self::Error> {
         sqlx::query!(
             r#"
                 SELECT *
                 FROM scratch_accounts
-                WHERE lower(username) = lower($1)
+                WHERE scratch_id = $2
+                    OR (lower(username) = lower($1) AND (scratch_id IS NULL OR $2 IS NULL))
+                ORDER BY scratch_id = $2 DESC NULLS LAST
+                LIMIT 1
             "#,
             username,
+            scratch_id,
         )
         .map(|user| ScratchAccount {
             username: user.username,
             id: user.id.parse().unwrap(),
+            scratch_id: user.scratch_id,
+            linked_at: user.linked_at,
+            verified_via: user.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: user.missing_checks,
+            gone_at: user.gone_at,
+            last_checked_at: user.last_checked_at,
         })
         .fetch_optional(self)
         .await
@@ -153,12 +375,48 @@ where
                 SELECT *
                 FROM scratch_accounts
                 WHERE id = $1
+                ORDER BY linked_at NULLS LAST, username
             "#,
             id.to_string(),
         )
         .map(|user| ScratchAccount {
             username: user.username,
             id: user.id.parse().unwrap(),
+            scratch_id: user.scratch_id,
+            linked_at: user.linked_at,
+            verified_via: user.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: user.missing_checks,
+            gone_at: user.gone_at,
+            last_checked_at: user.last_checked_at,
+        })
+        .fetch_all(self)
+        .await
+    }
+
+    async fn get_linked_scratch_accounts_of(
+        self,
+        ids: &[Id<UserMarker>],
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        let ids: Vec<_> = ids.iter().map(|id| id.to_string()).collect();
+
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM scratch_accounts
+                WHERE id = ANY($1)
+                ORDER BY id, username
+            "#,
+            &ids,
+        )
+        .map(|user| ScratchAccount {
+            username: user.username,
+            id: user.id.parse().unwrap(),
+            scratch_id: user.scratch_id,
+            linked_at: user.linked_at,
+            verified_via: user.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: user.missing_checks,
+            gone_at: user.gone_at,
+            last_checked_at: user.last_checked_at,
         })
         .fetch_all(self)
         .await
@@ -166,26 +424,194 @@ where
 
     async fn create_linked_scratch_account(
         self,
-        username: String,
-        id: Id<UserMarker>,
+        account: &ScratchAccount,
     ) -> Result<ScratchAccount, Self::Error> {
         sqlx::query!(
             r#"
-                INSERT INTO scratch_accounts (username, id)
-                VALUES ($1, $2)
+                INSERT INTO scratch_accounts (
+                    username, id, scratch_id, linked_at, verified_via, missing_checks, gone_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
                 RETURNING *
             "#,
-            username.to_string(),
-            id.to_string(),
+            account.username,
+            account.id.to_string(),
+            account.scratch_id,
+            account.linked_at,
+            account.verified_via.map(i16::from),
+            account.missing_checks,
+            account.gone_at,
         )
         .map(|row| ScratchAccount {
             username: row.username,
             id: row.id.parse().unwrap(),
+            scratch_id: row.scratch_id,
+            linked_at: row.linked_at,
+            verified_via: row.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: row.missing_checks,
+            gone_at: row.gone_at,
+            last_checked_at: row.last_checked_at,
         })
         .fetch_one(self)
         .await
     }
 
+    async fn delete_stale_scratch_account(
+        self,
+        username: String,
+        scratch_id: i64,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        sqlx::query!(
+            r#"
+                DELETE FROM scratch_accounts
+                WHERE lower(username) = lower($1) AND scratch_id <> $2
+                RETURNING *
+            "#,
+            username,
+            scratch_id,
+        )
+        .map(|row| ScratchAccount {
+            username: row.username,
+            id: row.id.parse().unwrap(),
+            scratch_id: row.scratch_id,
+            linked_at: row.linked_at,
+            verified_via: row.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: row.missing_checks,
+            gone_at: row.gone_at,
+            last_checked_at: row.last_checked_at,
+        })
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn get_scratch_accounts_without_scratch_id(
+        self,
+        after: String,
+        limit: i64,
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM scratch_accounts
+                WHERE scratch_id IS NULL AND username > $1
+                ORDER BY username
+                LIMIT $2
+            "#,
+            after,
+            limit,
+        )
+        .map(|row| ScratchAccount {
+            username: row.username,
+            id: row.id.parse().unwrap(),
+            scratch_id: row.scratch_id,
+            linked_at: row.linked_at,
+            verified_via: row.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: row.missing_checks,
+            gone_at: row.gone_at,
+            last_checked_at: row.last_checked_at,
+        })
+        .fetch_all(self)
+        .await
+    }
+
+    async fn set_scratch_id(
+        self,
+        username: String,
+        scratch_id: i64,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        sqlx::query!(
+            r#"
+                UPDATE scratch_accounts
+                SET scratch_id = $2
+                WHERE lower(username) = lower($1)
+                RETURNING *
+            "#,
+            username,
+            scratch_id,
+        )
+        .map(|row| ScratchAccount {
+            username: row.username,
+            id: row.id.parse().unwrap(),
+            scratch_id: row.scratch_id,
+            linked_at: row.linked_at,
+            verified_via: row.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: row.missing_checks,
+            gone_at: row.gone_at,
+            last_checked_at: row.last_checked_at,
+        })
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn get_scratch_accounts_to_check(
+        self,
+        before: OffsetDateTime,
+    ) -> Result<Vec<ScratchAccount>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM scratch_accounts
+                WHERE last_checked_at IS NULL OR last_checked_at < $1
+                ORDER BY last_checked_at NULLS FIRST, username
+            "#,
+            before,
+        )
+        .map(|row| ScratchAccount {
+            username: row.username,
+            id: row.id.parse().unwrap(),
+            scratch_id: row.scratch_id,
+            linked_at: row.linked_at,
+            verified_via: row.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: row.missing_checks,
+            gone_at: row.gone_at,
+            last_checked_at: row.last_checked_at,
+        })
+        .fetch_all(self)
+        .await
+    }
+
+    async fn record_scratch_account_check(
+        self,
+        username: String,
+        found: bool,
+        threshold: i16,
+    ) -> Result<Option<ScratchAccount>, Self::Error> {
+        // The count stops at the threshold, so that it can't overflow
+        sqlx::query!(
+            r#"
+                UPDATE scratch_accounts
+                SET
+                    missing_checks = CASE
+                        WHEN $2 THEN 0
+                        ELSE least(missing_checks + 1, $3::smallint)
+                    END,
+                    gone_at = CASE
+                        WHEN $2 THEN NULL
+                        WHEN missing_checks + 1 >= $3 THEN coalesce(gone_at, now())
+                        ELSE gone_at
+                    END,
+                    last_checked_at = now()
+                WHERE lower(username) = lower($1)
+                RETURNING *
+            "#,
+            username,
+            found,
+            threshold,
+        )
+        .map(|row| ScratchAccount {
+            username: row.username,
+            id: row.id.parse().unwrap(),
+            scratch_id: row.scratch_id,
+            linked_at: row.linked_at,
+            verified_via: row.verified_via.map(|via| via.try_into().unwrap()),
+            missing_checks: row.missing_checks,
+            gone_at: row.gone_at,
+            last_checked_at: row.last_checked_at,
+        })
+        .fetch_optional(self)
+        .await
+    }
+
     async fn transfer_linked_scratch_accounts(
         self,
         from: Id<UserMarker>,
@@ -194,12 +620,13 @@ where
         sqlx::query!(
             r#"
                 UPDATE scratch_accounts
-                SET id = $2
+                SET id = $2, linked_at = now(), verified_via = $3
                 WHERE id = $1
                 RETURNING username
             "#,
             from.to_string(),
             to.to_string(),
+            i16::from(VerifiedVia::Transfer),
         )
         .map(|row| row.username)
         .fetch_all(self)
@@ -276,8 +703,7 @@ where
         self,
         id: Id<UserMarker>,
     ) -> Result<Option<RoleConnectionData>, Self::Error> {
-        sqlx::query_as!(
-            RoleConnectionData,
+        sqlx::query!(
             r#"
                 SELECT scratcher, followers, joined
                 FROM metadata
@@ -285,17 +711,24 @@ where
             "#,
             id.to_string(),
         )
+        .map(|row| {
+            Some(RoleConnectionData {
+                scratcher: row.scratcher?,
+                followers: row.followers?,
+                joined: row.joined?,
+            })
+        })
         .fetch_optional(self)
         .await
+        .map(Option::flatten)
     }
 
     async fn write_metadata(
         self,
         id: Id<UserMarker>,
-        data: &RoleConnectionData,
-    ) -> Result<RoleConnectionData, Self::Error> {
-        sqlx::query_as!(
-            RoleConnectionData,
+        data: Option<&RoleConnectionData>,
+    ) -> Result<Option<RoleConnectionData>, Self::Error> {
+        sqlx::query!(
             r#"
                 UPDATE metadata SET
                     scratcher = $2,
@@ -306,13 +739,457 @@ where
                 RETURNING scratcher, followers, joined
             "#,
             id.to_string(),
-            data.scratcher,
-            data.followers,
-            data.joined,
+            data.map(|data| data.scratcher),
+            data.map(|data| data.followers),
+            data.map(|data| data.joined),
+        )
+        .map(|row| {
+            Some(RoleConnectionData {
+                scratcher: row.scratcher?,
+                followers: row.followers?,
+                joined: row.joined?,
+            })
+        })
+        .fetch_one(self)
+        .await
+    }
+
+    async fn write_stats_snapshot(
+        self,
+        snapshot: &StatsSnapshot,
+    ) -> Result<StatsSnapshot, Self::Error> {
+        sqlx::query!(
+            r#"
+                INSERT INTO stats_history (username, recorded_at, followers, status)
+                VALUES ($1, $2, $3, $4)
+                RETURNING *
+            "#,
+            snapshot.username,
+            snapshot.recorded_at,
+            snapshot.followers,
+            snapshot.status.clone().map(i16::from),
+        )
+        .map(|row| StatsSnapshot {
+            username: row.username,
+            recorded_at: row.recorded_at,
+            followers: row.followers,
+            status: row.status.map(|status| status.try_into().unwrap()),
+        })
+        .fetch_one(self)
+        .await
+    }
+
+    async fn get_stats_history(
+        self,
+        username: String,
+        limit: i64,
+    ) -> Result<Vec<StatsSnapshot>, Self::Error> {
+        let mut history = sqlx::query!(
+            r#"
+                SELECT *
+                FROM stats_history
+                WHERE lower(username) = lower($1)
+                ORDER BY recorded_at DESC
+                LIMIT $2
+            "#,
+            username,
+            limit,
+        )
+        .map(|row| StatsSnapshot {
+            username: row.username,
+            recorded_at: row.recorded_at,
+            followers: row.followers,
+            status: row.status.map(|status| status.try_into().unwrap()),
+        })
+        .fetch_all(self)
+        .await?;
+
+        history.reverse();
+
+        Ok(history)
+    }
+
+    async fn delete_stats_snapshots_before(
+        self,
+        before: OffsetDateTime,
+    ) -> Result<u64, Self::Error> {
+        Ok(sqlx::query!(
+            r#"
+                DELETE FROM stats_history
+                WHERE recorded_at < $1
+            "#,
+            before,
+        )
+        .execute(self)
+        .await?
+        .rows_affected())
+    }
+
+    async fn get_pending_verification(
+        self,
+        id: Id<UserMarker>,
+        username: String,
+    ) -> Result<Option<PendingVerification>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM pending_verifications
+                WHERE id = $1 AND lower(username) = lower($2)
+            "#,
+            id.to_string(),
+            username,
+        )
+        .map(|row| PendingVerification {
+            id: row.id.parse().unwrap(),
+            username: row.username,
+            code: row.code,
+            method: row.method.try_into().unwrap(),
+            generated_at: row.generated_at,
+            expires_at: row.expires_at,
+            interaction_token: row.interaction_token,
+            locale: row.locale,
+            studio_id: row.studio_id,
+            guild_id: row.guild_id.map(|id| id.parse().unwrap()),
+        })
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn get_active_pending_verifications(
+        self,
+    ) -> Result<Vec<PendingVerification>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM pending_verifications
+                WHERE expires_at >= now()
+            "#
+        )
+        .map(|row| PendingVerification {
+            id: row.id.parse().unwrap(),
+            username: row.username,
+            code: row.code,
+            method: row.method.try_into().unwrap(),
+            generated_at: row.generated_at,
+            expires_at: row.expires_at,
+            interaction_token: row.interaction_token,
+            locale: row.locale,
+            studio_id: row.studio_id,
+            guild_id: row.guild_id.map(|id| id.parse().unwrap()),
+        })
+        .fetch_all(self)
+        .await
+    }
+
+    async fn write_pending_verification(
+        self,
+        verification: &PendingVerification,
+    ) -> Result<PendingVerification, Self::Error> {
+        sqlx::query!(
+            r#"
+                INSERT INTO pending_verifications (
+                    id, username, code, method, generated_at, expires_at, interaction_token, locale,
+                    studio_id, guild_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (id) DO UPDATE SET
+                    username = EXCLUDED.username,
+                    code = EXCLUDED.code,
+                    method = EXCLUDED.method,
+                    generated_at = EXCLUDED.generated_at,
+                    expires_at = EXCLUDED.expires_at,
+                    interaction_token = EXCLUDED.interaction_token,
+                    locale = EXCLUDED.locale,
+                    studio_id = EXCLUDED.studio_id,
+                    guild_id = EXCLUDED.guild_id
+                RETURNING *
+            "#,
+            verification.id.to_string(),
+            verification.username,
+            verification.code,
+            i16::from(verification.method),
+            verification.generated_at,
+            verification.expires_at,
+            verification.interaction_token,
+            verification.locale,
+            verification.studio_id,
+            verification.guild_id.map(|id| id.to_string()),
+        )
+        .map(|row| PendingVerification {
+            id: row.id.parse().unwrap(),
+            username: row.username,
+            code: row.code,
+            method: row.method.try_into().unwrap(),
+            generated_at: row.generated_at,
+            expires_at: row.expires_at,
+            interaction_token: row.interaction_token,
+            locale: row.locale,
+            studio_id: row.studio_id,
+            guild_id: row.guild_id.map(|id| id.parse().unwrap()),
+        })
+        .fetch_one(self)
+        .await
+    }
+
+    async fn delete_pending_verification(
+        self,
+        id: Id<UserMarker>,
+        username: String,
+    ) -> Result<Option<PendingVerification>, Self::Error> {
+        sqlx::query!(
+            r#"
+                DELETE FROM pending_verifications
+                WHERE id = $1 AND lower(username) = lower($2)
+                RETURNING *
+            "#,
+            id.to_string(),
+            username,
+        )
+        .map(|row| PendingVerification {
+            id: row.id.parse().unwrap(),
+            username: row.username,
+            code: row.code,
+            method: row.method.try_into().unwrap(),
+            generated_at: row.generated_at,
+            expires_at: row.expires_at,
+            interaction_token: row.interaction_token,
+            locale: row.locale,
+            studio_id: row.studio_id,
+            guild_id: row.guild_id.map(|id| id.parse().unwrap()),
+        })
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn delete_expired_pending_verifications(self) -> Result<u64, Self::Error> {
+        Ok(sqlx::query!(
+            r#"
+                DELETE FROM pending_verifications
+                WHERE expires_at < now()
+            "#
+        )
+        .execute(self)
+        .await?
+        .rows_affected())
+    }
+
+    async fn get_role_rules(self) -> Result<Vec<RoleRule>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM role_rules
+                ORDER BY guild_id, role_id
+            "#
+        )
+        .map(|row| RoleRule {
+            guild_id: row.guild_id.parse().unwrap(),
+            role_id: row.role_id.parse().unwrap(),
+            condition: Condition::from_parts(row.condition, row.value).unwrap(),
+        })
+        .fetch_all(self)
+        .await
+    }
+
+    async fn get_guild_role_rules(
+        self,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<Vec<RoleRule>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM role_rules
+                WHERE guild_id = $1
+                ORDER BY role_id
+            "#,
+            guild_id.to_string(),
         )
+        .map(|row| RoleRule {
+            guild_id: row.guild_id.parse().unwrap(),
+            role_id: row.role_id.parse().unwrap(),
+            condition: Condition::from_parts(row.condition, row.value).unwrap(),
+        })
+        .fetch_all(self)
+        .await
+    }
+
+    async fn write_role_rule(self, rule: &RoleRule) -> Result<RoleRule, Self::Error> {
+        let (condition, value) = rule.condition.into_parts();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO role_rules (guild_id, role_id, condition, value)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (guild_id, role_id) DO UPDATE SET
+                    condition = EXCLUDED.condition,
+                    value = EXCLUDED.value
+                RETURNING *
+            "#,
+            rule.guild_id.to_string(),
+            rule.role_id.to_string(),
+            condition,
+            value,
+        )
+        .map(|row| RoleRule {
+            guild_id: row.guild_id.parse().unwrap(),
+            role_id: row.role_id.parse().unwrap(),
+            condition: Condition::from_parts(row.condition, row.value).unwrap(),
+        })
         .fetch_one(self)
         .await
     }
+
+    async fn delete_role_rule(
+        self,
+        guild_id: Id<GuildMarker>,
+        role_id: Id<RoleMarker>,
+    ) -> Result<Option<RoleRule>, Self::Error> {
+        sqlx::query!(
+            r#"
+                DELETE FROM role_rules
+                WHERE guild_id = $1 AND role_id = $2
+                RETURNING *
+            "#,
+            guild_id.to_string(),
+            role_id.to_string(),
+        )
+        .map(|row| RoleRule {
+            guild_id: row.guild_id.parse().unwrap(),
+            role_id: row.role_id.parse().unwrap(),
+            condition: Condition::from_parts(row.condition, row.value).unwrap(),
+        })
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn get_guild_config(
+        self,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<Option<GuildConfig>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT *
+                FROM guild_config
+                WHERE guild_id = $1
+            "#,
+            guild_id.to_string(),
+        )
+        .map(|row| GuildConfig {
+            guild_id: row.guild_id.parse().unwrap(),
+            log_channel_id: row.log_channel_id.map(|id| id.parse().unwrap()),
+            ephemeral: row.ephemeral,
+            locale: row.locale,
+            disabled_commands: row.disabled_commands,
+            sync_nicknames: row.sync_nicknames,
+        })
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn write_guild_config(self, config: &GuildConfig) -> Result<GuildConfig, Self::Error> {
+        sqlx::query!(
+            r#"
+                INSERT INTO guild_config (
+                    guild_id, log_channel_id, ephemeral, locale, disabled_commands,
+                    sync_nicknames
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (guild_id) DO UPDATE SET
+                    log_channel_id = EXCLUDED.log_channel_id,
+                    ephemeral = EXCLUDED.ephemeral,
+                    locale = EXCLUDED.locale,
+                    disabled_commands = EXCLUDED.disabled_commands,
+                    sync_nicknames = EXCLUDED.sync_nicknames
+                RETURNING *
+            "#,
+            config.guild_id.to_string(),
+            config.log_channel_id.map(|id| id.to_string()),
+            config.ephemeral,
+            config.locale,
+            &config.disabled_commands,
+            config.sync_nicknames,
+        )
+        .map(|row| GuildConfig {
+            guild_id: row.guild_id.parse().unwrap(),
+            log_channel_id: row.log_channel_id.map(|id| id.parse().unwrap()),
+            ephemeral: row.ephemeral,
+            locale: row.locale,
+            disabled_commands: row.disabled_commands,
+            sync_nicknames: row.sync_nicknames,
+        })
+        .fetch_one(self)
+        .await
+    }
+
+    async fn get_guilds_with_log_channel(self) -> Result<Vec<Id<GuildMarker>>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT guild_id
+                FROM guild_config
+                WHERE log_channel_id IS NOT NULL
+                ORDER BY guild_id
+            "#
+        )
+        .map(|row| row.guild_id.parse().unwrap())
+        .fetch_all(self)
+        .await
+    }
+
+    async fn get_applied_migrations(self) -> Result<Vec<i64>, Self::Error> {
+        sqlx::query!(
+            r#"
+                SELECT version
+                FROM _sqlx_migrations
+                WHERE success
+                ORDER BY version
+            "#
+        )
+        .map(|row| row.version)
+        .fetch_all(self)
+        .await
+    }
+}
+
+/// Databases which can run several queries atomically,
+/// so that logic like [`link_account`] works with any backend.
+#[async_trait]
+pub trait Transactional: Sync {
+    type Transaction: DatabaseTransaction;
+
+    async fn transaction(&self) -> Result<Self::Transaction, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait DatabaseTransaction: Send {
+    type Executor<'e>: Database<Error = sqlx::Error> + Send
+    where
+        Self: 'e;
+
+    fn executor(&mut self) -> Self::Executor<'_>;
+
+    /// Dropping the transaction without committing rolls it back.
+    async fn commit(self) -> Result<(), sqlx::Error>;
+}
+
+#[async_trait]
+impl Transactional for PgPool {
+    type Transaction = sqlx::Transaction<'static, Postgres>;
+
+    async fn transaction(&self) -> Result<Self::Transaction, sqlx::Error> {
+        self.begin().await
+    }
+}
+
+#[async_trait]
+impl DatabaseTransaction for sqlx::Transaction<'static, Postgres> {
+    type Executor<'e> = &'e mut PgConnection;
+
+    fn executor(&mut self) -> Self::Executor<'_> {
+        self
+    }
+
+    async fn commit(self) -> Result<(), sqlx::Error> {
+        sqlx::Transaction::commit(self).await
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -321,14 +1198,73 @@ pub enum LinkError {
     AlreadyLinkedToOther(Id<UserMarker>),
 }
 
-pub async fn link_account(
-    pool: &PgPool,
+/// Links the Scratch account, `scratch_id` should be given if the Scratch API returned it.
+///
+/// Also returns the stale link of a deleted account with the same username, if it was removed.
+pub async fn link_account<P: Transactional>(
+    pool: &P,
     username: String,
+    scratch_id: Option<i64>,
     id: Id<UserMarker>,
-) -> Result<Result<(), LinkError>, sqlx::Error> {
-    let mut tx = pool.begin().await?;
+    via: VerifiedVia,
+) -> Result<Result<(ScratchAccount, Option<ScratchAccount>), LinkError>, sqlx::Error> {
+    let mut tx = pool.transaction().await?;
+
+    let result = link(&mut tx, username, scratch_id, id, via).await?;
+
+    if result.is_ok() {
+        tx.commit().await?;
+    }
+
+    Ok(result)
+}
+
+/// Removes the pending verification and links its account, both or neither.
+///
+/// `None` if the verification was already removed, for example completed by another task.
+pub async fn complete_verification<P: Transactional>(
+    pool: &P,
+    verification: &PendingVerification,
+    scratch_id: Option<i64>,
+) -> Result<Option<Result<(ScratchAccount, Option<ScratchAccount>), LinkError>>, sqlx::Error> {
+    let mut tx = pool.transaction().await?;
+
+    if tx
+        .executor()
+        .delete_pending_verification(verification.id, verification.username.to_owned())
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    let result = link(
+        &mut tx,
+        verification.username.to_owned(),
+        scratch_id,
+        verification.id,
+        verification.method.into(),
+    )
+    .await?;
+
+    // The verification is used up even if the account can't be linked
+    tx.commit().await?;
+
+    Ok(Some(result))
+}
 
-    if let Some(already_linked) = tx.get_scratch_account(username.to_owned()).await? {
+async fn link<T: DatabaseTransaction>(
+    tx: &mut T,
+    username: String,
+    scratch_id: Option<i64>,
+    id: Id<UserMarker>,
+    via: VerifiedVia,
+) -> Result<Result<(ScratchAccount, Option<ScratchAccount>), LinkError>, sqlx::Error> {
+    if let Some(already_linked) = tx
+        .executor()
+        .get_scratch_account(username.to_owned(), scratch_id)
+        .await?
+    {
         if already_linked.id == id {
             return Ok(Err(LinkError::AlreadyLinkedToYou));
         } else {
@@ -336,15 +1272,35 @@ pub async fn link_account(
         }
     }
 
-    if tx.get_discord_account(id).await?.is_none() {
-        tx.create_discord_account(id).await?;
-    }
+    // Otherwise the username would still be taken
+    let stale = match scratch_id {
+        Some(scratch_id) => {
+            tx.executor()
+                .delete_stale_scratch_account(username.to_owned(), scratch_id)
+                .await?
+        }
+        None => None,
+    };
 
-    tx.create_linked_scratch_account(username, id).await?;
+    if tx.executor().get_discord_account(id).await?.is_none() {
+        tx.executor().create_discord_account(id).await?;
+    }
 
-    tx.commit().await?;
+    let account = tx
+        .executor()
+        .create_linked_scratch_account(&ScratchAccount {
+            username,
+            id,
+            scratch_id,
+            linked_at: Some(OffsetDateTime::now_utc()),
+            verified_via: Some(via),
+            missing_checks: 0,
+            gone_at: None,
+            last_checked_at: None,
+        })
+        .await?;
 
-    Ok(Ok(()))
+    Ok(Ok((account, stale)))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -353,14 +1309,18 @@ pub enum TransferError {
     NotLinked,
 }
 
-pub async fn transfer_linked_accounts(
-    pool: &PgPool,
+pub async fn transfer_linked_accounts<P: Transactional>(
+    pool: &P,
     username: String,
     id: Id<UserMarker>,
 ) -> Result<Result<(Id<UserMarker>, Vec<String>), TransferError>, sqlx::Error> {
-    let mut tx = pool.begin().await?;
+    let mut tx = pool.transaction().await?;
 
-    let already_linked = match tx.get_scratch_account(username.to_owned()).await? {
+    let already_linked = match tx
+        .executor()
+        .get_scratch_account(username.to_owned(), None)
+        .await?
+    {
         Some(already_linked) => {
             if already_linked.id == id {
                 return Ok(Err(TransferError::AlreadyLinkedToYou));
@@ -371,11 +1331,12 @@ pub async fn transfer_linked_accounts(
         None => return Ok(Err(TransferError::NotLinked)),
     };
 
-    if tx.get_discord_account(id).await?.is_none() {
-        tx.create_discord_account(id).await?;
+    if tx.executor().get_discord_account(id).await?.is_none() {
+        tx.executor().create_discord_account(id).await?;
     }
 
     let transferred = tx
+        .executor()
         .transfer_linked_scratch_accounts(already_linked.id, id)
         .await?;
 
@@ -1,200 +1,590 @@
+use time::{macros::datetime, Duration, OffsetDateTime};
+
 use super::*;
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn get_account(pool: PgPool) {
-    let account = pool
-        .get_scratch_account("pmj_studio".to_string())
-        .await
-        .unwrap();
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn get_account(pool) {
+        let account = pool
+            .get_scratch_account("pmj_studio".to_string(), None)
+            .await
+            .unwrap();
 
-    assert_eq!(
-        account,
-        Some(ScratchAccount {
-            id: "755497867606622450".parse().unwrap(),
-            username: "PMJ_Studio".to_string()
-        }),
-        "case insensitive username",
-    );
+        assert_eq!(
+            account,
+            Some(ScratchAccount {
+                id: "755497867606622450".parse().unwrap(),
+                scratch_id: Some(42178181),
+                linked_at: Some(datetime!(2023-05-16 12:00:00 UTC)),
+                verified_via: Some(VerifiedVia::Comment),
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+                username: "PMJ_Studio".to_string()
+            }),
+            "case insensitive username",
+        );
+
+        let account = pool
+            .get_scratch_account("a".to_string(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(account, None, "nonexistent Scratch account");
 
-    let account = pool.get_scratch_account("a".to_string()).await.unwrap();
+        let account = pool
+            .get_discord_account("755497867606622450".parse().unwrap())
+            .await
+            .unwrap();
 
-    assert_eq!(account, None, "nonexistent Scratch account");
+        assert_eq!(
+            account,
+            Some(DiscordAccount {
+                id: "755497867606622450".parse().unwrap()
+            }),
+            "Discord account",
+        );
+
+        let account = pool
+            .get_discord_account("855497867606622450".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(account, None, "nonexistent Discord account");
+
+        let mut linked_accounts = pool
+            .get_linked_scratch_accounts("755497867606622450".parse().unwrap())
+            .await
+            .unwrap();
+
+        linked_accounts.sort_by_key(|account| account.username.to_string());
+
+        assert_eq!(
+            linked_accounts,
+            vec![
+                ScratchAccount {
+                    username: "PMJ_Studio".to_string(),
+                    id: "755497867606622450".parse().unwrap(),
+                    scratch_id: Some(42178181),
+                    linked_at: Some(datetime!(2023-05-16 12:00:00 UTC)),
+                    verified_via: Some(VerifiedVia::Comment),
+                    missing_checks: 0,
+                    gone_at: None,
+                    last_checked_at: None,
+                },
+                ScratchAccount {
+                    username: "PMJ_test".to_string(),
+                    id: "755497867606622450".parse().unwrap(),
+                    scratch_id: None,
+                    linked_at: None,
+                    verified_via: None,
+                    missing_checks: 0,
+                    gone_at: None,
+                    last_checked_at: None,
+                }
+            ],
+            "linked Scratch accounts",
+        );
+    }
+}
 
-    let account = pool
-        .get_discord_account("755497867606622450".parse().unwrap())
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn get_linked_scratch_accounts_ordered(pool) {
+        pool.create_linked_scratch_account(&ScratchAccount {
+            username: "PMJ_JPB14".to_string(),
+            id: "755497867606622450".parse().unwrap(),
+            scratch_id: None,
+            linked_at: Some(datetime!(2023-04-01 12:00:00 UTC)),
+            verified_via: Some(VerifiedVia::Admin),
+            missing_checks: 0,
+            gone_at: None,
+            last_checked_at: None,
+        })
         .await
         .unwrap();
 
-    assert_eq!(
-        account,
-        Some(DiscordAccount {
-            id: "755497867606622450".parse().unwrap()
-        }),
-        "Discord account",
-    );
+        let usernames: Vec<_> = pool
+            .get_linked_scratch_accounts("755497867606622450".parse().unwrap())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|account| account.username)
+            .collect();
+
+        assert_eq!(
+            usernames,
+            vec!["PMJ_JPB14", "PMJ_Studio", "PMJ_test"],
+            "earliest linked first, never linked last",
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn get_linked_scratch_accounts_of(pool) {
+        let linked_accounts = pool
+            .get_linked_scratch_accounts_of(&[
+                "775316334259077120".parse().unwrap(),
+                "855497867606622450".parse().unwrap(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            linked_accounts,
+            vec![ScratchAccount {
+                username: "PMJ_MJBCS27".to_string(),
+                id: "775316334259077120".parse().unwrap(),
+                scratch_id: None,
+                linked_at: None,
+                verified_via: None,
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+            }],
+            "only linked users",
+        );
+
+        let linked_accounts = pool.get_linked_scratch_accounts_of(&[]).await.unwrap();
+
+        assert_eq!(linked_accounts, vec![], "no users");
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn create_discord_account(pool) {
+        pool.create_discord_account("755497867606622450".parse().unwrap())
+            .await
+            .expect_err("can't create account with already used ID");
+
+        let created_account = pool
+            .create_discord_account("855497867606622450".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            created_account,
+            DiscordAccount {
+                id: "855497867606622450".parse().unwrap()
+            },
+            "create Discord account",
+        );
+
+        let read_account = pool
+            .get_discord_account("855497867606622450".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            read_account,
+            Some(created_account),
+            "successfully created Discord account",
+        );
+    }
+}
 
-    let account = pool
-        .get_discord_account("855497867606622450".parse().unwrap())
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn create_linked_scratch_account(pool) {
+        pool.create_linked_scratch_account(&ScratchAccount {
+            username: "PMJ_Studio".to_string(),
+            id: "755497867606622450".parse().unwrap(),
+            scratch_id: None,
+            linked_at: None,
+            verified_via: None,
+            missing_checks: 0,
+            gone_at: None,
+            last_checked_at: None,
+        })
         .await
-        .unwrap();
+        .expect_err("can't link already linked account to the same user");
 
-    assert_eq!(account, None, "nonexistent Discord account");
+        pool.create_linked_scratch_account(&ScratchAccount {
+            username: "PMJ_MJBCS27".to_string(),
+            id: "755497867606622450".parse().unwrap(),
+            scratch_id: None,
+            linked_at: None,
+            verified_via: None,
+            missing_checks: 0,
+            gone_at: None,
+            last_checked_at: None,
+        })
+        .await
+        .expect_err("can't link already linked account to other user");
 
-    let mut linked_accounts = pool
-        .get_linked_scratch_accounts("755497867606622450".parse().unwrap())
+        pool.create_linked_scratch_account(&ScratchAccount {
+            username: "PMJ_JPB14".to_string(),
+            id: "855497867606622450".parse().unwrap(),
+            scratch_id: None,
+            linked_at: None,
+            verified_via: None,
+            missing_checks: 0,
+            gone_at: None,
+            last_checked_at: None,
+        })
         .await
-        .unwrap();
+        .expect_err("can't link to a nonexistent user");
 
-    linked_accounts.sort_by_key(|account| account.username.to_string());
+        pool.create_linked_scratch_account(&ScratchAccount {
+            username: "PMJ_JPB14".to_string(),
+            id: "755497867606622450".parse().unwrap(),
+            scratch_id: Some(42178181),
+            linked_at: None,
+            verified_via: None,
+            missing_checks: 0,
+            gone_at: None,
+            last_checked_at: None,
+        })
+        .await
+        .expect_err("can't link with an already linked Scratch ID");
 
-    assert_eq!(
-        linked_accounts,
-        vec![
-            ScratchAccount {
-                username: "PMJ_Studio".to_string(),
+        let linked_account = pool
+            .create_linked_scratch_account(&ScratchAccount {
+                username: "PMJ_JPB14".to_string(),
                 id: "755497867606622450".parse().unwrap(),
-            },
+                scratch_id: Some(42178189),
+                linked_at: Some(datetime!(2023-08-03 12:00:00 UTC)),
+                verified_via: Some(VerifiedVia::Admin),
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            linked_account,
             ScratchAccount {
-                username: "PMJ_test".to_string(),
+                username: "PMJ_JPB14".to_string(),
                 id: "755497867606622450".parse().unwrap(),
-            }
-        ],
-        "linked Scratch accounts",
-    );
-}
+                scratch_id: Some(42178189),
+                linked_at: Some(datetime!(2023-08-03 12:00:00 UTC)),
+                verified_via: Some(VerifiedVia::Admin),
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+            },
+            "successfully linked Scratch account",
+        );
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn create_discord_account(pool: PgPool) {
-    pool.create_discord_account("755497867606622450".parse().unwrap())
-        .await
-        .expect_err("can't create account with already used ID");
+        let mut linked_accounts = pool
+            .get_linked_scratch_accounts("755497867606622450".parse().unwrap())
+            .await
+            .unwrap();
 
-    let created_account = pool
-        .create_discord_account("855497867606622450".parse().unwrap())
+        linked_accounts.sort_by_key(|account| account.username.to_string());
+
+        assert_eq!(
+            linked_accounts,
+            vec![
+                ScratchAccount {
+                    username: "PMJ_JPB14".to_string(),
+                    id: "755497867606622450".parse().unwrap(),
+                    scratch_id: Some(42178189),
+                    linked_at: Some(datetime!(2023-08-03 12:00:00 UTC)),
+                    verified_via: Some(VerifiedVia::Admin),
+                    missing_checks: 0,
+                    gone_at: None,
+                    last_checked_at: None,
+                },
+                ScratchAccount {
+                    username: "PMJ_Studio".to_string(),
+                    id: "755497867606622450".parse().unwrap(),
+                    scratch_id: Some(42178181),
+                    linked_at: Some(datetime!(2023-05-16 12:00:00 UTC)),
+                    verified_via: Some(VerifiedVia::Comment),
+                    missing_checks: 0,
+                    gone_at: None,
+                    last_checked_at: None,
+                },
+                ScratchAccount {
+                    username: "PMJ_test".to_string(),
+                    id: "755497867606622450".parse().unwrap(),
+                    scratch_id: None,
+                    linked_at: None,
+                    verified_via: None,
+                    missing_checks: 0,
+                    gone_at: None,
+                    last_checked_at: None,
+                },
+            ],
+            "linked Scratch accounts",
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn test_link_account(pool) {
+        let result = link_account(
+            &pool,
+            "PMJ_Studio".to_string(),
+            Some(42178181),
+            "755497867606622450".parse().unwrap(),
+            VerifiedVia::Comment,
+        )
         .await
         .unwrap();
 
-    assert_eq!(
-        created_account,
-        DiscordAccount {
-            id: "855497867606622450".parse().unwrap()
-        },
-        "create Discord account",
-    );
+        assert_eq!(result, Err(LinkError::AlreadyLinkedToYou));
 
-    let read_account = pool
-        .get_discord_account("855497867606622450".parse().unwrap())
+        let result = link_account(
+            &pool,
+            "PMJ_MJBCS27".to_string(),
+            None,
+            "755497867606622450".parse().unwrap(),
+            VerifiedVia::Comment,
+        )
         .await
         .unwrap();
 
-    assert_eq!(
-        read_account,
-        Some(created_account),
-        "successfully created Discord account",
-    );
-}
+        assert_eq!(
+            result,
+            Err(LinkError::AlreadyLinkedToOther(
+                "775316334259077120".parse().unwrap()
+            ))
+        );
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn create_linked_scratch_account(pool: PgPool) {
-    pool.create_linked_scratch_account(
-        "PMJ_Studio".to_string(),
-        "755497867606622450".parse().unwrap(),
-    )
-    .await
-    .expect_err("can't link already linked account to the same user");
-
-    pool.create_linked_scratch_account(
-        "PMJ_MJBCS27".to_string(),
-        "755497867606622450".parse().unwrap(),
-    )
-    .await
-    .expect_err("can't link already linked account to other user");
-
-    pool.create_linked_scratch_account(
-        "PMJ_JPB14".to_string(),
-        "855497867606622450".parse().unwrap(),
-    )
-    .await
-    .expect_err("can't link to a nonexistent user");
-
-    let linked_account = pool
-        .create_linked_scratch_account(
+        let (account, stale) = link_account(
+            &pool,
             "PMJ_JPB14".to_string(),
+            None,
             "755497867606622450".parse().unwrap(),
+            VerifiedVia::Comment,
         )
         .await
+        .unwrap()
         .unwrap();
 
-    assert_eq!(
-        linked_account,
-        ScratchAccount {
-            username: "PMJ_JPB14".to_string(),
-            id: "755497867606622450".parse().unwrap(),
-        },
-        "successfully linked Scratch account",
-    );
+        assert_eq!(stale, None);
+        assert_eq!(account.username, "PMJ_JPB14");
+        assert_eq!(account.verified_via, Some(VerifiedVia::Comment));
+        assert!(
+            OffsetDateTime::now_utc() - account.linked_at.unwrap() < Duration::minutes(1),
+            "linked now",
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn get_account_by_scratch_id(pool) {
+        let account = pool
+            .get_scratch_account("someone_else".to_string(), Some(42178181))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            account.map(|account| account.username),
+            Some("PMJ_Studio".to_string()),
+            "ID preferred over username",
+        );
 
-    let mut linked_accounts = pool
-        .get_linked_scratch_accounts("755497867606622450".parse().unwrap())
+        let account = pool
+            .get_scratch_account("pmj_studio".to_string(), Some(42178189))
+            .await
+            .unwrap();
+
+        assert_eq!(account, None, "recreated account with the same username");
+
+        let account = pool
+            .get_scratch_account("pmj_test".to_string(), Some(42178189))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            account.map(|account| account.username),
+            Some("PMJ_test".to_string()),
+            "username if the ID isn't known yet",
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn link_recreated_account(pool) {
+        let result = link_account(
+            &pool,
+            "pmj_studio".to_string(),
+            Some(42178189),
+            "775316334259077120".parse().unwrap(),
+            VerifiedVia::Cloud,
+        )
         .await
         .unwrap();
 
-    linked_accounts.sort_by_key(|account| account.username.to_string());
+        let (_, stale) = result.unwrap();
 
-    assert_eq!(
-        linked_accounts,
-        vec![
-            ScratchAccount {
-                username: "PMJ_JPB14".to_string(),
-                id: "755497867606622450".parse().unwrap(),
-            },
-            ScratchAccount {
-                username: "PMJ_Studio".to_string(),
-                id: "755497867606622450".parse().unwrap(),
-            },
-            ScratchAccount {
-                username: "PMJ_test".to_string(),
-                id: "755497867606622450".parse().unwrap(),
-            },
-        ],
-        "linked Scratch accounts",
-    );
+        assert_eq!(
+            stale.map(|account| (account.username, account.id)),
+            Some((
+                "PMJ_Studio".to_string(),
+                "755497867606622450".parse().unwrap(),
+            )),
+            "stale link replaced",
+        );
+
+        let account = pool
+            .get_scratch_account("PMJ_Studio".to_string(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            account.map(|account| (account.username, account.scratch_id, account.verified_via)),
+            Some((
+                "pmj_studio".to_string(),
+                Some(42178189),
+                Some(VerifiedVia::Cloud),
+            )),
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn backfill_scratch_ids(pool) {
+        let accounts = pool
+            .get_scratch_accounts_without_scratch_id(String::new(), 1)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            accounts.iter().map(|account| account.username.as_str()).collect::<Vec<_>>(),
+            vec!["PMJ_MJBCS27"],
+            "first batch",
+        );
+
+        let accounts = pool
+            .get_scratch_accounts_without_scratch_id("PMJ_MJBCS27".to_string(), 1)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            accounts.iter().map(|account| account.username.as_str()).collect::<Vec<_>>(),
+            vec!["PMJ_test"],
+            "next batch",
+        );
+
+        let account = pool
+            .set_scratch_id("pmj_test".to_string(), 42178189)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            account.and_then(|account| account.scratch_id),
+            Some(42178189),
+            "case insensitive username",
+        );
+
+        let accounts = pool
+            .get_scratch_accounts_without_scratch_id("PMJ_MJBCS27".to_string(), 1)
+            .await
+            .unwrap();
+
+        assert_eq!(accounts, vec![], "already backfilled");
+
+        let account = pool
+            .set_scratch_id("PMJ_JPB14".to_string(), 42178190)
+            .await
+            .unwrap();
+
+        assert_eq!(account, None, "not linked");
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn record_scratch_account_check(pool) {
+        for checks in 1..=2 {
+            let account = pool
+                .record_scratch_account_check("PMJ_test".to_string(), false, 3)
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(account.missing_checks, checks);
+            assert_eq!(account.gone_at, None, "not gone yet");
+        }
+
+        let account = pool
+            .record_scratch_account_check("PMJ_test".to_string(), false, 3)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(account.missing_checks, 3);
+        let gone_at = account.gone_at.expect("gone after the threshold");
+
+        let account = pool
+            .record_scratch_account_check("PMJ_test".to_string(), false, 3)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(account.missing_checks, 3, "stops at the threshold");
+        assert_eq!(account.gone_at, Some(gone_at), "gone since the first time");
+
+        let account = pool
+            .record_scratch_account_check("pmj_test".to_string(), true, 3)
+            .await
+            .unwrap()
+            .expect("case insensitive username");
+
+        assert_eq!(account.missing_checks, 0);
+        assert_eq!(account.gone_at, None, "found again");
+        assert!(
+            OffsetDateTime::now_utc() - account.last_checked_at.unwrap() < Duration::minutes(1),
+            "checked now",
+        );
+
+        let account = pool
+            .record_scratch_account_check("PMJ_JPB14".to_string(), false, 3)
+            .await
+            .unwrap();
+
+        assert_eq!(account, None, "not linked");
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn test_link_account(pool: PgPool) {
-    let result = link_account(
-        &pool,
-        "PMJ_Studio".to_string(),
-        "755497867606622450".parse().unwrap(),
-    )
-    .await
-    .unwrap();
-
-    assert_eq!(result, Err(LinkError::AlreadyLinkedToYou));
-
-    let result = link_account(
-        &pool,
-        "PMJ_MJBCS27".to_string(),
-        "755497867606622450".parse().unwrap(),
-    )
-    .await
-    .unwrap();
-
-    assert_eq!(
-        result,
-        Err(LinkError::AlreadyLinkedToOther(
-            "775316334259077120".parse().unwrap()
-        ))
-    );
-
-    let result = link_account(
-        &pool,
-        "PMJ_JPB14".to_string(),
-        "755497867606622450".parse().unwrap(),
-    )
-    .await
-    .unwrap();
-
-    assert_eq!(result, Ok(()));
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn get_scratch_accounts_to_check(pool) {
+        // Without the metadata fixture, like users who never authorized Linked Roles
+        pool.record_scratch_account_check("PMJ_test".to_string(), true, 3)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let now = OffsetDateTime::now_utc();
+
+        let usernames: Vec<_> = pool
+            .get_scratch_accounts_to_check(now + Duration::minutes(1))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|account| account.username)
+            .collect();
+
+        assert_eq!(
+            usernames,
+            vec!["PMJ_MJBCS27", "PMJ_Studio", "PMJ_test"],
+            "never checked first",
+        );
+
+        let usernames: Vec<_> = pool
+            .get_scratch_accounts_to_check(now - Duration::minutes(1))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|account| account.username)
+            .collect();
+
+        assert_eq!(
+            usernames,
+            vec!["PMJ_MJBCS27", "PMJ_Studio"],
+            "already checked",
+        );
+    }
 }
@@ -0,0 +1,4 @@
+INSERT INTO
+	guild_config (guild_id, log_channel_id, ephemeral, locale, disabled_commands)
+VALUES
+	('1042113658546147378', '1042113658546147382', true, 'pl', '{"project"}');
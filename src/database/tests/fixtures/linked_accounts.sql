@@ -5,8 +5,8 @@ VALUES
 	('775316334259077120');
 
 INSERT INTO
-	scratch_accounts (username, id)
+	scratch_accounts (username, id, scratch_id, linked_at, verified_via)
 VALUES
-	('PMJ_Studio', '755497867606622450'),
-	('PMJ_test', '755497867606622450'),
-	('PMJ_MJBCS27', '775316334259077120');
+	('PMJ_Studio', '755497867606622450', 42178181, '2023-05-16 12:00:00+00', 0),
+	('PMJ_test', '755497867606622450', NULL, NULL, NULL),
+	('PMJ_MJBCS27', '775316334259077120', NULL, NULL, NULL);
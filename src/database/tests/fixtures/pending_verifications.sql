@@ -0,0 +1,5 @@
+INSERT INTO
+	pending_verifications (id, username, code, method, generated_at, expires_at)
+VALUES
+	('755497867606622450', 'PMJ_JPB14', 'K7QD 3XMB 9TZH', 0, '2023-08-03 12:00:00 z', '2023-08-03 12:05:00 z'),
+	('775316334259077120', 'PMJ_JPB14', '4821736590', 1, '2023-08-03 12:00:00 z', '2999-08-03 12:05:00 z');
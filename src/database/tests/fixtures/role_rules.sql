@@ -0,0 +1,6 @@
+INSERT INTO
+	role_rules (guild_id, role_id, condition, value)
+VALUES
+	('1042113658546147378', '1042113658546147380', 0, 1),
+	('1042113658546147378', '1042113658546147381', 1, 100),
+	('1119332463536169020', '1119332463536169022', 2, 365);
@@ -0,0 +1,72 @@
+use super::*;
+
+fn configured() -> GuildConfig {
+    GuildConfig {
+        guild_id: "1042113658546147378".parse().unwrap(),
+        log_channel_id: Some("1042113658546147382".parse().unwrap()),
+        ephemeral: true,
+        locale: Some("pl".into()),
+        disabled_commands: vec!["project".into()],
+        sync_nicknames: false,
+    }
+}
+
+database_test! {
+    #[fixtures("guild_config")]
+    async fn get_guild_config(pool) {
+        let actual = pool
+            .get_guild_config("1042113658546147378".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Some(configured()));
+
+        let actual = pool
+            .get_guild_config("1119332463536169020".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "never configured");
+    }
+}
+
+database_test! {
+    #[fixtures("guild_config")]
+    async fn write_guild_config(pool) {
+        let expected = GuildConfig::new("1119332463536169020".parse().unwrap());
+
+        let actual = pool.write_guild_config(&expected).await.unwrap();
+
+        assert_eq!(actual, expected, "insert");
+
+        let expected = GuildConfig {
+            log_channel_id: None,
+            disabled_commands: Vec::new(),
+            sync_nicknames: true,
+            ..configured()
+        };
+
+        let actual = pool.write_guild_config(&expected).await.unwrap();
+
+        assert_eq!(actual, expected, "update");
+    }
+}
+
+database_test! {
+    #[fixtures("guild_config")]
+    async fn get_guilds_with_log_channel(pool) {
+        pool.write_guild_config(&GuildConfig::new("1119332463536169020".parse().unwrap()))
+            .await
+            .unwrap();
+
+        let guild_ids = pool.get_guilds_with_log_channel().await.unwrap();
+
+        assert_eq!(guild_ids, vec![configured().guild_id], "only with a log channel");
+    }
+}
+
+#[test]
+fn is_enabled() {
+    assert!(configured().is_enabled("user"));
+    assert!(!configured().is_enabled("project"));
+}
@@ -0,0 +1,142 @@
+//! The rows of the SQL fixtures, for [`MemoryDatabase`].
+
+use time::macros::datetime;
+
+use super::*;
+
+pub async fn load(pool: &MemoryDatabase, fixture: &str) {
+    let mut tables = pool.tables().await;
+
+    match fixture {
+        "guild_config" => {
+            let mut config = GuildConfig::new("1042113658546147378".parse().unwrap());
+            config.log_channel_id = Some("1042113658546147382".parse().unwrap());
+            config.ephemeral = true;
+            config.locale = Some("pl".into());
+            config.disabled_commands = vec!["project".into()];
+
+            tables.guild_config.insert(config.guild_id, config);
+        }
+        "linked_accounts" => {
+            tables
+                .discord_accounts
+                .insert("755497867606622450".parse().unwrap());
+            tables
+                .discord_accounts
+                .insert("775316334259077120".parse().unwrap());
+
+            tables.scratch_accounts.push(ScratchAccount {
+                username: "PMJ_Studio".into(),
+                id: "755497867606622450".parse().unwrap(),
+                scratch_id: Some(42178181),
+                linked_at: Some(datetime!(2023-05-16 12:00:00 UTC)),
+                verified_via: Some(VerifiedVia::Comment),
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+            });
+
+            for (username, id) in [
+                ("PMJ_test", "755497867606622450"),
+                ("PMJ_MJBCS27", "775316334259077120"),
+            ] {
+                tables.scratch_accounts.push(ScratchAccount {
+                    username: username.into(),
+                    id: id.parse().unwrap(),
+                    scratch_id: None,
+                    linked_at: None,
+                    verified_via: None,
+                    missing_checks: 0,
+                    gone_at: None,
+                    last_checked_at: None,
+                });
+            }
+        }
+        "metadata" => {
+            tables.metadata.insert(
+                "755497867606622450".parse().unwrap(),
+                (
+                    Some(RoleConnectionData {
+                        scratcher: true,
+                        followers: 1000,
+                        joined: datetime!(2020-08-03 12:00:00 UTC),
+                    }),
+                    datetime!(2023-08-03 12:00:00 UTC),
+                ),
+            );
+            tables.metadata.insert(
+                "775316334259077120".parse().unwrap(),
+                (
+                    Some(RoleConnectionData {
+                        scratcher: true,
+                        followers: 900,
+                        joined: datetime!(2021-08-03 12:00:00 UTC),
+                    }),
+                    datetime!(2023-08-03 12:01:00 UTC),
+                ),
+            );
+        }
+        "pending_verifications" => {
+            tables.pending_verifications.push(PendingVerification {
+                id: "755497867606622450".parse().unwrap(),
+                username: "PMJ_JPB14".into(),
+                code: "K7QD 3XMB 9TZH".into(),
+                method: Method::Comment,
+                generated_at: datetime!(2023-08-03 12:00:00 UTC),
+                expires_at: datetime!(2023-08-03 12:05:00 UTC),
+                interaction_token: None,
+                locale: None,
+                studio_id: None,
+                guild_id: None,
+            });
+            tables.pending_verifications.push(PendingVerification {
+                id: "775316334259077120".parse().unwrap(),
+                username: "PMJ_JPB14".into(),
+                code: "4821736590".into(),
+                method: Method::Cloud,
+                generated_at: datetime!(2023-08-03 12:00:00 UTC),
+                expires_at: datetime!(2999-08-03 12:05:00 UTC),
+                interaction_token: None,
+                locale: None,
+                studio_id: None,
+                guild_id: None,
+            });
+        }
+        "role_rules" => {
+            for (guild_id, role_id, condition) in [
+                (
+                    "1042113658546147378",
+                    "1042113658546147380",
+                    Condition::Scratcher,
+                ),
+                (
+                    "1042113658546147378",
+                    "1042113658546147381",
+                    Condition::Followers(100),
+                ),
+                (
+                    "1119332463536169020",
+                    "1119332463536169022",
+                    Condition::JoinedDaysAgo(365),
+                ),
+            ] {
+                tables.role_rules.push(RoleRule {
+                    guild_id: guild_id.parse().unwrap(),
+                    role_id: role_id.parse().unwrap(),
+                    condition,
+                });
+            }
+        }
+        "tokens" => {
+            tables.tokens.insert(
+                "755497867606622450".parse().unwrap(),
+                Token {
+                    access_token: "access_token".into(),
+                    refresh_token: "refresh_token".into(),
+                    expires_at: datetime!(2023-07-10 12:00:00 UTC),
+                },
+            );
+        }
+        _ => panic!("unknown fixture {fixture}"),
+    }
+}
@@ -1,54 +1,121 @@
 use time::macros::datetime;
 
+use crate::linked_roles::save_metadata;
+
 use super::*;
 
-#[sqlx::test(fixtures("linked_accounts", "metadata"))]
-async fn get_oldest_metadata(pool: PgPool) {
-    let expected = Some((
-        "755497867606622450".parse().unwrap(),
-        datetime!(2023-08-03 12:00:00 UTC),
-    ));
+database_test! {
+    #[fixtures("linked_accounts", "metadata")]
+    async fn get_oldest_metadata(pool) {
+        let expected = Some((
+            "755497867606622450".parse().unwrap(),
+            datetime!(2023-08-03 12:00:00 UTC),
+        ));
+
+        let actual = pool.get_oldest_metadata().await.unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
 
-    let actual = pool.get_oldest_metadata().await.unwrap();
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn get_oldest_metadata_none(pool) {
+        let actual = pool.get_oldest_metadata().await.unwrap();
 
-    assert_eq!(actual, expected);
+        assert_eq!(actual, None);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn get_oldest_metadata_none(pool: PgPool) {
-    let actual = pool.get_oldest_metadata().await.unwrap();
+database_test! {
+    #[fixtures("linked_accounts", "metadata")]
+    async fn get_metadata(pool) {
+        let expected = Some(RoleConnectionData {
+            scratcher: true,
+            followers: 1000,
+            joined: datetime!(2020-08-03 12:00:00 UTC),
+        });
+
+        let actual = pool
+            .get_metadata("755497867606622450".parse().unwrap())
+            .await
+            .unwrap();
 
-    assert_eq!(actual, None);
+        assert_eq!(actual, expected);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts", "metadata"))]
-async fn get_metadata(pool: PgPool) {
-    let expected = Some(RoleConnectionData {
-        scratcher: true,
-        followers: 1000,
-        joined: datetime!(2020-08-03 12:00:00 UTC),
-    });
+database_test! {
+    #[fixtures("linked_accounts", "metadata")]
+    async fn write_metadata(pool) {
+        let expected = RoleConnectionData {
+            scratcher: true,
+            followers: 1001,
+            joined: datetime!(2020-08-03 12:00:00 UTC),
+        };
 
-    let actual = pool
-        .get_metadata("755497867606622450".parse().unwrap())
-        .await
-        .unwrap();
+        let actual = pool
+            .write_metadata("755497867606622450".parse().unwrap(), Some(&expected))
+            .await
+            .unwrap();
 
-    assert_eq!(actual, expected);
+        assert_eq!(actual, Some(expected));
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts", "metadata"))]
-async fn write_metadata(pool: PgPool) {
-    let expected = RoleConnectionData {
-        scratcher: true,
-        followers: 1001,
-        joined: datetime!(2020-08-03 12:00:00 UTC),
-    };
+database_test! {
+    #[fixtures("linked_accounts", "metadata")]
+    async fn write_metadata_cleared(pool) {
+        let id = "755497867606622450".parse().unwrap();
+
+        let actual = pool.write_metadata(id, None).await.unwrap();
+
+        assert_eq!(actual, None);
+
+        let actual = pool.get_metadata(id).await.unwrap();
+
+        assert_eq!(actual, None, "cleared values");
+
+        let oldest = pool.get_oldest_metadata().await.unwrap();
+
+        assert_eq!(
+            oldest.map(|(id, _)| id),
+            Some("775316334259077120".parse().unwrap()),
+            "updated even though cleared",
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "metadata")]
+    async fn save_metadata_changed(pool) {
+        let id = "755497867606622450".parse().unwrap();
+
+        let data = pool.get_metadata(id).await.unwrap().unwrap();
+
+        let changed = save_metadata(&pool, id, Some(&data)).await.unwrap();
+
+        assert!(!changed, "same data");
+
+        let data = RoleConnectionData {
+            followers: 1001,
+            ..data
+        };
+
+        let changed = save_metadata(&pool, id, Some(&data)).await.unwrap();
+
+        assert!(changed, "more followers");
+
+        let actual = pool.get_metadata(id).await.unwrap();
+
+        assert_eq!(actual, Some(data));
+
+        let changed = save_metadata(&pool, id, None).await.unwrap();
+
+        assert!(changed, "no accounts count anymore");
 
-    let actual = pool
-        .write_metadata("755497867606622450".parse().unwrap(), &expected)
-        .await
-        .unwrap();
+        let changed = save_metadata(&pool, id, None).await.unwrap();
 
-    assert_eq!(actual, expected);
+        assert!(!changed, "still cleared");
+    }
 }
@@ -0,0 +1,15 @@
+use super::*;
+
+database_test! {
+    async fn get_applied_migrations(pool) {
+        let expected: Vec<_> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .collect();
+
+        let actual = pool.get_applied_migrations().await.unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,6 +1,45 @@
+/// Runs the test against Postgres with the SQL fixtures, and against [`MemoryDatabase`] with the
+/// same rows from [`memory_fixtures`], as the `postgres` and `memory` tests in a module named
+/// after the test.
+macro_rules! database_test {
+    (
+        $(#[fixtures($($fixture:literal),+)])?
+        async fn $name:ident($pool:ident) $body:block
+    ) => {
+        mod $name {
+            use super::*;
+
+            async fn test<P>($pool: P)
+            where
+                P: crate::database::Transactional,
+                for<'a> &'a P: crate::database::Database<Error = sqlx::Error>,
+            $body
+
+            #[sqlx::test$((fixtures($($fixture),+)))?]
+            async fn postgres(pool: sqlx::PgPool) {
+                test(pool).await;
+            }
+
+            #[tokio::test]
+            async fn memory() {
+                let pool = crate::database::MemoryDatabase::default();
+                $($(crate::database::tests::memory_fixtures::load(&pool, $fixture).await;)+)?
+                test(pool).await;
+            }
+        }
+    };
+}
+
 mod discord_scratch;
+mod guild_config;
+mod memory_fixtures;
 mod metadata;
+mod migrations;
+mod pending_verification;
+mod role_rule;
+mod stats_history;
 mod token;
+mod transaction;
 mod transfer;
 
 use sqlx::PgPool;
@@ -10,15 +49,40 @@ use super::*;
 /// Just to make sure it compiles with all the lifetimes
 #[allow(dead_code)]
 async fn lifetime_compile_test(pool: PgPool) {
-    pool.get_scratch_account("username".into()).await.unwrap();
+    pool.get_scratch_account("username".into(), None)
+        .await
+        .unwrap();
 
     let mut tx = pool.begin().await.unwrap();
 
-    tx.get_scratch_account("username".into()).await.unwrap();
+    tx.get_scratch_account("username".into(), None)
+        .await
+        .unwrap();
+
+    pool.get_scratch_account("username".into(), None)
+        .await
+        .unwrap();
+
+    tx.get_scratch_account("username".into(), None)
+        .await
+        .unwrap();
+
+    tx.commit().await.unwrap();
+}
+
+/// Same for the in-memory database
+#[allow(dead_code)]
+async fn memory_lifetime_compile_test(pool: MemoryDatabase) {
+    pool.get_scratch_account("username".into(), None)
+        .await
+        .unwrap();
 
-    pool.get_scratch_account("username".into()).await.unwrap();
+    let mut tx = pool.transaction().await.unwrap();
 
-    tx.get_scratch_account("username".into()).await.unwrap();
+    tx.executor()
+        .get_scratch_account("username".into(), None)
+        .await
+        .unwrap();
 
     tx.commit().await.unwrap();
 }
@@ -0,0 +1,207 @@
+use time::macros::datetime;
+
+use super::*;
+
+fn expired() -> PendingVerification {
+    PendingVerification {
+        id: "755497867606622450".parse().unwrap(),
+        username: "PMJ_JPB14".into(),
+        code: "K7QD 3XMB 9TZH".into(),
+        method: Method::Comment,
+        generated_at: datetime!(2023-08-03 12:00:00 UTC),
+        expires_at: datetime!(2023-08-03 12:05:00 UTC),
+        interaction_token: None,
+        locale: None,
+        studio_id: None,
+        guild_id: None,
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn get_pending_verification(pool) {
+        let actual = pool
+            .get_pending_verification("755497867606622450".parse().unwrap(), "pmj_jpb14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Some(expired()), "case insensitive username");
+
+        let actual = pool
+            .get_pending_verification("755497867606622450".parse().unwrap(), "PMJ_Studio".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "nonexistent pending verification");
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn get_active_pending_verifications(pool) {
+        let actual = pool.get_active_pending_verifications().await.unwrap();
+
+        assert_eq!(
+            actual,
+            vec![PendingVerification {
+                id: "775316334259077120".parse().unwrap(),
+                code: "4821736590".into(),
+                method: Method::Cloud,
+                expires_at: datetime!(2999-08-03 12:05:00 UTC),
+                ..expired()
+            }]
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn write_pending_verification_replaces(pool) {
+        let expected = PendingVerification {
+            username: "pmj_jpb14".into(),
+            code: "4821736590".into(),
+            method: Method::Cloud,
+            generated_at: datetime!(2023-08-03 13:00:00 UTC),
+            expires_at: datetime!(2023-08-03 13:05:00 UTC),
+            interaction_token: Some("interaction_token".into()),
+            locale: Some("pl".into()),
+            studio_id: Some(29137750),
+            guild_id: Some("1042113658546147378".parse().unwrap()),
+            ..expired()
+        };
+
+        let actual = pool.write_pending_verification(&expected).await.unwrap();
+
+        assert_eq!(actual, expected);
+
+        let actual = pool
+            .get_pending_verification("755497867606622450".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            Some(expected),
+            "one pending verification per account"
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn write_pending_verification_other_account(pool) {
+        let expected = PendingVerification {
+            username: "PMJ_Studio".into(),
+            ..expired()
+        };
+
+        pool.write_pending_verification(&expected).await.unwrap();
+
+        let actual = pool
+            .get_pending_verification("755497867606622450".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "one pending verification per Discord user");
+
+        let actual = pool
+            .get_pending_verification("755497867606622450".parse().unwrap(), "PMJ_Studio".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Some(expected));
+
+        let actual = pool
+            .get_pending_verification("775316334259077120".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert!(
+            actual.is_some(),
+            "another user can verify the same account, only one of them can post the code"
+        );
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn delete_pending_verification(pool) {
+        let actual = pool
+            .delete_pending_verification("755497867606622450".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Some(expired()));
+
+        let actual = pool
+            .delete_pending_verification("755497867606622450".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "already deleted");
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn delete_expired_pending_verifications(pool) {
+        let deleted = pool.delete_expired_pending_verifications().await.unwrap();
+
+        assert_eq!(deleted, 1);
+
+        let actual = pool
+            .get_pending_verification("775316334259077120".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert!(actual.is_some(), "not expired yet");
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts", "pending_verifications")]
+    async fn complete_verification_once(pool) {
+        let (account, _) = complete_verification(&pool, &expired(), None)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(account.username, "PMJ_JPB14");
+        assert_eq!(account.verified_via, Some(VerifiedVia::Comment));
+
+        let actual = pool
+            .get_pending_verification("755497867606622450".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "removed together with linking");
+
+        let result = complete_verification(&pool, &expired(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(result, None, "already completed by another task");
+
+        let other = PendingVerification {
+            id: "775316334259077120".parse().unwrap(),
+            method: Method::Cloud,
+            ..expired()
+        };
+        let result = complete_verification(&pool, &other, None).await.unwrap();
+
+        assert_eq!(
+            result,
+            Some(Err(LinkError::AlreadyLinkedToOther(
+                "755497867606622450".parse().unwrap()
+            )))
+        );
+
+        let actual = pool
+            .get_pending_verification("775316334259077120".parse().unwrap(), "PMJ_JPB14".into())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "removed even though linking failed");
+    }
+}
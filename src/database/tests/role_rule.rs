@@ -0,0 +1,90 @@
+use crate::roles::Condition;
+
+use super::*;
+
+fn scratcher() -> RoleRule {
+    RoleRule {
+        guild_id: "1042113658546147378".parse().unwrap(),
+        role_id: "1042113658546147380".parse().unwrap(),
+        condition: Condition::Scratcher,
+    }
+}
+
+fn followers() -> RoleRule {
+    RoleRule {
+        role_id: "1042113658546147381".parse().unwrap(),
+        condition: Condition::Followers(100),
+        ..scratcher()
+    }
+}
+
+fn joined() -> RoleRule {
+    RoleRule {
+        guild_id: "1119332463536169020".parse().unwrap(),
+        role_id: "1119332463536169022".parse().unwrap(),
+        condition: Condition::JoinedDaysAgo(365),
+    }
+}
+
+database_test! {
+    #[fixtures("role_rules")]
+    async fn get_role_rules(pool) {
+        let actual = pool.get_role_rules().await.unwrap();
+
+        assert_eq!(actual, vec![scratcher(), followers(), joined()]);
+    }
+}
+
+database_test! {
+    #[fixtures("role_rules")]
+    async fn get_guild_role_rules(pool) {
+        let actual = pool
+            .get_guild_role_rules("1042113658546147378".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, vec![scratcher(), followers()]);
+    }
+}
+
+database_test! {
+    #[fixtures("role_rules")]
+    async fn write_role_rule_replaces(pool) {
+        let expected = RoleRule {
+            condition: Condition::Followers(1000),
+            ..followers()
+        };
+
+        let actual = pool.write_role_rule(&expected).await.unwrap();
+
+        assert_eq!(actual, expected);
+
+        let actual = pool
+            .get_guild_role_rules("1042113658546147378".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(actual, vec![scratcher(), expected], "one rule per role");
+    }
+}
+
+database_test! {
+    #[fixtures("role_rules")]
+    async fn delete_role_rule(pool) {
+        let rule = followers();
+
+        let actual = pool
+            .delete_role_rule(rule.guild_id, rule.role_id)
+            .await
+            .unwrap();
+
+        assert_eq!(actual, Some(rule.to_owned()));
+
+        let actual = pool
+            .delete_role_rule(rule.guild_id, rule.role_id)
+            .await
+            .unwrap();
+
+        assert_eq!(actual, None, "already deleted");
+    }
+}
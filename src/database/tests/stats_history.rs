@@ -0,0 +1,117 @@
+use time::macros::datetime;
+
+use crate::scratch::db::user::Status;
+
+use super::*;
+
+fn snapshot(username: &str, recorded_at: OffsetDateTime, followers: i64) -> StatsSnapshot {
+    StatsSnapshot {
+        username: username.into(),
+        recorded_at,
+        followers,
+        status: Some(Status::Scratcher),
+    }
+}
+
+database_test! {
+    async fn stats_history(pool) {
+        let snapshots = [
+            snapshot("PMJ_Studio", datetime!(2023-08-02 12:00:00 UTC), 110),
+            snapshot("PMJ_Studio", datetime!(2023-08-01 12:00:00 UTC), 100),
+            snapshot("PMJ_test", datetime!(2023-08-01 12:00:00 UTC), 5),
+            snapshot("PMJ_Studio", datetime!(2023-08-03 12:00:00 UTC), 120),
+        ];
+
+        for snapshot in &snapshots {
+            let written = pool.write_stats_snapshot(snapshot).await.unwrap();
+
+            assert_eq!(&written, snapshot);
+        }
+
+        let history = pool
+            .get_stats_history("pmj_studio".to_string(), 10)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            history.iter().map(|snapshot| snapshot.followers).collect::<Vec<_>>(),
+            vec![100, 110, 120],
+            "case insensitive, oldest first",
+        );
+
+        let history = pool
+            .get_stats_history("PMJ_Studio".to_string(), 2)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            history.iter().map(|snapshot| snapshot.followers).collect::<Vec<_>>(),
+            vec![110, 120],
+            "only the latest",
+        );
+
+        let history = pool
+            .get_stats_history("PMJ_JPB14".to_string(), 10)
+            .await
+            .unwrap();
+
+        assert_eq!(history, vec![], "no history");
+    }
+}
+
+database_test! {
+    async fn stats_snapshot_once(pool) {
+        let snapshot = snapshot("PMJ_Studio", datetime!(2023-08-01 12:00:00 UTC), 100);
+
+        pool.write_stats_snapshot(&snapshot).await.unwrap();
+
+        let err = pool.write_stats_snapshot(&snapshot).await.unwrap_err();
+
+        assert!(
+            err.as_database_error().unwrap().is_unique_violation(),
+            "same account at the same time",
+        );
+
+        let err = pool
+            .write_stats_snapshot(&StatsSnapshot {
+                username: "pmj_studio".into(),
+                ..snapshot
+            })
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.as_database_error().unwrap().is_unique_violation(),
+            "case insensitive username",
+        );
+    }
+}
+
+database_test! {
+    async fn delete_stats_snapshots_before(pool) {
+        for snapshot in [
+            snapshot("PMJ_Studio", datetime!(2023-05-01 12:00:00 UTC), 90),
+            snapshot("PMJ_Studio", datetime!(2023-08-01 12:00:00 UTC), 100),
+            snapshot("PMJ_test", datetime!(2023-05-02 12:00:00 UTC), 5),
+        ] {
+            pool.write_stats_snapshot(&snapshot).await.unwrap();
+        }
+
+        let deleted = pool
+            .delete_stats_snapshots_before(datetime!(2023-06-01 00:00:00 UTC))
+            .await
+            .unwrap();
+
+        assert_eq!(deleted, 2);
+
+        let history = pool
+            .get_stats_history("PMJ_Studio".to_string(), 10)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            history.iter().map(|snapshot| snapshot.followers).collect::<Vec<_>>(),
+            vec![100],
+        );
+    }
+}
@@ -1,102 +1,116 @@
-use sqlx::PgPool;
 use time::{macros::datetime, Duration, OffsetDateTime};
 
 use crate::{database::Database, linked_roles::Token};
 
-#[sqlx::test(fixtures("linked_accounts", "tokens"))]
-async fn read_existing_token(pool: PgPool) {
-    let id = "755497867606622450".parse().unwrap();
+database_test! {
+    #[fixtures("linked_accounts", "tokens")]
+    async fn read_existing_token(pool) {
+        let id = "755497867606622450".parse().unwrap();
 
-    let expected = Token {
-        access_token: "access_token".into(),
-        refresh_token: "refresh_token".into(),
-        expires_at: datetime!(2023-07-10 12:00:00 UTC),
-    };
+        let expected = Token {
+            access_token: "access_token".into(),
+            refresh_token: "refresh_token".into(),
+            expires_at: datetime!(2023-07-10 12:00:00 UTC),
+        };
 
-    let actual = pool.get_token(id).await.unwrap();
+        let actual = pool.get_token(id).await.unwrap();
 
-    assert_eq!(actual, Some(expected));
+        assert_eq!(actual, Some(expected));
+    }
 }
-#[sqlx::test(fixtures("linked_accounts", "tokens"))]
-async fn read_nonexistent_token(pool: PgPool) {
-    let id = "775316334259077120".parse().unwrap();
 
-    let actual = pool.get_token(id).await.unwrap();
+database_test! {
+    #[fixtures("linked_accounts", "tokens")]
+    async fn read_nonexistent_token(pool) {
+        let id = "775316334259077120".parse().unwrap();
 
-    assert_eq!(actual, None);
+        let actual = pool.get_token(id).await.unwrap();
+
+        assert_eq!(actual, None);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn write_token(pool: PgPool) {
-    let id = "755497867606622450".parse().unwrap();
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn write_token(pool) {
+        let id = "755497867606622450".parse().unwrap();
 
-    let token = Token {
-        access_token: "access_token".into(),
-        refresh_token: "refresh_token".into(),
-        expires_at: OffsetDateTime::now_utc() + Duration::seconds(10),
-    };
+        let token = Token {
+            access_token: "access_token".into(),
+            refresh_token: "refresh_token".into(),
+            expires_at: OffsetDateTime::now_utc() + Duration::seconds(10),
+        };
 
-    let expected = token.clone();
+        let expected = token.clone();
 
-    let actual = pool.write_token(id, token).await.unwrap();
+        let actual = pool.write_token(id, token).await.unwrap();
 
-    assert_eq!(actual, expected);
+        assert_eq!(actual, expected);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn write_token_expired(pool: PgPool) {
-    let id = "755497867606622450".parse().unwrap();
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn write_token_expired(pool) {
+        let id = "755497867606622450".parse().unwrap();
 
-    let token = Token {
-        access_token: "access_token".into(),
-        refresh_token: "refresh_token".into(),
-        expires_at: datetime!(2000-01-01 00:00:00 UTC),
-    };
+        let token = Token {
+            access_token: "access_token".into(),
+            refresh_token: "refresh_token".into(),
+            expires_at: datetime!(2000-01-01 00:00:00 UTC),
+        };
 
-    let expected = token.clone();
+        let expected = token.clone();
 
-    let actual = pool.write_token(id, token).await.unwrap();
+        let actual = pool.write_token(id, token).await.unwrap();
 
-    assert_eq!(actual, expected);
+        assert_eq!(actual, expected);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts", "tokens"))]
-async fn overwrite_existing_token(pool: PgPool) {
-    let id = "755497867606622450".parse().unwrap();
+database_test! {
+    #[fixtures("linked_accounts", "tokens")]
+    async fn overwrite_existing_token(pool) {
+        let id = "755497867606622450".parse().unwrap();
 
-    let token = Token {
-        access_token: "access_token".into(),
-        refresh_token: "refresh_token".into(),
-        expires_at: datetime!(2000-01-01 00:00:00 UTC),
-    };
+        let token = Token {
+            access_token: "access_token".into(),
+            refresh_token: "refresh_token".into(),
+            expires_at: datetime!(2000-01-01 00:00:00 UTC),
+        };
 
-    let expected = token.clone();
+        let expected = token.clone();
 
-    let actual = pool.write_token(id, token).await.unwrap();
+        let actual = pool.write_token(id, token).await.unwrap();
 
-    assert_eq!(actual, expected);
+        assert_eq!(actual, expected);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts", "tokens"))]
-async fn delete_existing_token(pool: PgPool) {
-    let id = "755497867606622450".parse().unwrap();
+database_test! {
+    #[fixtures("linked_accounts", "tokens")]
+    async fn delete_existing_token(pool) {
+        let id = "755497867606622450".parse().unwrap();
 
-    let expected = Token {
-        access_token: "access_token".into(),
-        refresh_token: "refresh_token".into(),
-        expires_at: datetime!(2023-07-10 12:00:00 UTC),
-    };
+        let expected = Token {
+            access_token: "access_token".into(),
+            refresh_token: "refresh_token".into(),
+            expires_at: datetime!(2023-07-10 12:00:00 UTC),
+        };
 
-    let actual = pool.delete_token(id).await.unwrap();
-    assert_eq!(actual, expected);
+        let actual = pool.delete_token(id).await.unwrap();
+        assert_eq!(actual, expected);
 
-    let actual = pool.get_token(id).await.unwrap();
-    assert_eq!(actual, None);
+        let actual = pool.get_token(id).await.unwrap();
+        assert_eq!(actual, None);
+    }
 }
 
-#[sqlx::test(fixtures("linked_accounts"))]
-async fn delete_nonexistent_token(pool: PgPool) {
-    let id = "755497867606622450".parse().unwrap();
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn delete_nonexistent_token(pool) {
+        let id = "755497867606622450".parse().unwrap();
 
-    pool.delete_token(id).await.unwrap_err();
+        pool.delete_token(id).await.unwrap_err();
+    }
 }
@@ -0,0 +1,74 @@
+use super::*;
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn commit(pool) {
+        let id = "855497867606622450".parse().unwrap();
+
+        let mut tx = pool.transaction().await.unwrap();
+        tx.executor().create_discord_account(id).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let account = pool.get_discord_account(id).await.unwrap();
+
+        assert_eq!(account, Some(DiscordAccount { id }));
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn rollback(pool) {
+        let id = "855497867606622450".parse().unwrap();
+
+        let mut tx = pool.transaction().await.unwrap();
+        tx.executor().create_discord_account(id).await.unwrap();
+        drop(tx);
+
+        let account = pool.get_discord_account(id).await.unwrap();
+
+        assert_eq!(account, None, "dropped without committing");
+    }
+}
+
+database_test! {
+    #[fixtures("linked_accounts")]
+    async fn constraint_errors(pool) {
+        let err = pool
+            .create_linked_scratch_account(&ScratchAccount {
+                username: "pmj_studio".to_string(),
+                id: "775316334259077120".parse().unwrap(),
+                scratch_id: None,
+                linked_at: None,
+                verified_via: None,
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+            })
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.as_database_error().unwrap().is_unique_violation(),
+            "case insensitive unique username",
+        );
+
+        let err = pool
+            .create_linked_scratch_account(&ScratchAccount {
+                username: "PMJ_JPB14".to_string(),
+                id: "855497867606622450".parse().unwrap(),
+                scratch_id: None,
+                linked_at: None,
+                verified_via: None,
+                missing_checks: 0,
+                gone_at: None,
+                last_checked_at: None,
+            })
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.as_database_error().unwrap().is_foreign_key_violation(),
+            "nonexistent Discord account",
+        );
+    }
+}
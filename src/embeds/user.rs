@@ -1,14 +1,16 @@
 use time::OffsetDateTime;
+use tiny_skia::Pixmap;
 use twilight_model::channel::message::embed::EmbedAuthor;
 use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
 
 use crate::{
+    card::{Card, MAX_RANKS},
     locales::{Locale, ToLocalized},
     scratch::{
         api,
         db::{
             self,
-            user::{Statistics, Status},
+            user::{Ranks, Statistics, Status},
         },
     },
 };
@@ -19,6 +21,7 @@ use super::{timestamp, trim_field, Extend};
 pub struct User {
     username: Option<String>,
     image: Option<String>,
+    avatar: Option<String>,
     joined: Option<OffsetDateTime>,
     country: Option<String>,
     about: Option<String>,
@@ -32,12 +35,71 @@ impl User {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// URL of the bigger profile picture, drawn on the card.
+    pub fn avatar(&self) -> Option<&str> {
+        self.avatar.as_deref()
+    }
+
+    /// The avatar has to be downloaded separately, see [`User::avatar`].
+    pub fn card(&self, locale: Locale, avatar: Option<Pixmap>) -> Card {
+        let ranks = self
+            .statistics
+            .as_ref()
+            .map(|stats| top_ranks(&stats.ranks, locale))
+            .unwrap_or_default();
+
+        Card {
+            username: self.username.clone().unwrap_or_default(),
+            avatar,
+            status: self
+                .status
+                .as_ref()
+                .map(|status| status.to_localized(locale)),
+            followers: self
+                .statistics
+                .as_ref()
+                .map(|stats| locale.stats_followers(&stats.followers.to_string())),
+            joined: self
+                .joined
+                .map(|joined| locale.card_joined(&joined.date().to_string())),
+            ranks,
+        }
+    }
+}
+
+/// The best global ranks, 0 means that ScratchDB didn't rank the user.
+fn top_ranks(ranks: &Ranks, locale: Locale) -> Vec<String> {
+    let rank = |rank: i64| format!("#{rank}");
+    let mut ranks = [
+        (
+            ranks.followers,
+            locale.stats_followers(&rank(ranks.followers)),
+        ),
+        (ranks.loves, locale.stats_loves(&rank(ranks.loves))),
+        (
+            ranks.favorites,
+            locale.stats_favorites(&rank(ranks.favorites)),
+        ),
+        (ranks.views, locale.stats_views(&rank(ranks.views))),
+        (ranks.comments, locale.stats_comments(&rank(ranks.comments))),
+    ];
+    // Stable, so followers come first on a tie
+    ranks.sort_by_key(|(rank, _)| *rank);
+
+    ranks
+        .into_iter()
+        .filter(|(rank, _)| *rank > 0)
+        .take(MAX_RANKS)
+        .map(|(_, text)| text)
+        .collect()
 }
 
 impl Extend<api::User> for User {
     fn extend(&mut self, data: api::User) -> &mut Self {
         self.username = Some(data.username);
         self.image = Some(data.profile.images.n50x50);
+        self.avatar = Some(data.profile.images.n90x90);
         self.joined = Some(data.history.joined);
         self.country = data.profile.country;
         self.about = Some(data.profile.bio).filter(|s| !s.is_empty());
@@ -0,0 +1,202 @@
+//! Events posted to the log channel configured with `/config log-channel`.
+
+use std::time::Duration;
+
+use time::OffsetDateTime;
+use tracing::{debug, error, warn};
+use twilight_http::error::ErrorType;
+use twilight_mention::Mention;
+use twilight_model::{
+    channel::message::{AllowedMentions, Embed},
+    id::{
+        marker::{GuildMarker, UserMarker},
+        Id,
+    },
+    util::Timestamp,
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+
+use crate::{
+    embeds::{self, Color},
+    locales::Locale,
+    scratch::site::user_link,
+    state::AppState,
+    verification::VerifiedVia,
+};
+
+/// Attempts before giving up on posting an event.
+const ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Linked {
+        id: Id<UserMarker>,
+        username: String,
+        linked_at: OffsetDateTime,
+        via: VerifiedVia,
+    },
+    /// The code was posted by another account
+    WrongAccount {
+        id: Id<UserMarker>,
+        expected: String,
+        actual: String,
+    },
+    WrongCode {
+        id: Id<UserMarker>,
+        username: String,
+    },
+    /// The linked account wasn't found on Scratch for a few days
+    AccountGone {
+        id: Id<UserMarker>,
+        username: String,
+    },
+    /// Verifying an account linked to another Discord account moved all of its accounts
+    Transferred {
+        from: Id<UserMarker>,
+        to: Id<UserMarker>,
+        usernames: Vec<String>,
+    },
+    /// The link of a deleted account was removed when its username was linked again
+    Unlinked {
+        id: Id<UserMarker>,
+        username: String,
+    },
+}
+
+impl Event {
+    fn to_embed(&self, locale: Locale) -> anyhow::Result<Embed> {
+        let (title, description, color) = match self {
+            Self::Linked { id, username, .. } => (
+                locale.log_linked_title(),
+                locale.log_linked(&id.mention().to_string(), &user_link(username)),
+                Color::Success,
+            ),
+            Self::WrongAccount {
+                id,
+                expected,
+                actual,
+            } => (
+                locale.log_verification_failed_title(),
+                locale.log_wrong_account(
+                    &user_link(actual),
+                    &user_link(expected),
+                    &id.mention().to_string(),
+                ),
+                Color::Error,
+            ),
+            Self::WrongCode { id, username } => (
+                locale.log_verification_failed_title(),
+                locale.log_wrong_code(&id.mention().to_string(), &user_link(username)),
+                Color::Error,
+            ),
+            Self::AccountGone { id, username } => (
+                locale.log_account_gone_title(),
+                locale.log_account_gone(&id.mention().to_string(), &user_link(username)),
+                Color::Error,
+            ),
+            Self::Transferred {
+                from,
+                to,
+                usernames,
+            } => (
+                locale.log_transferred_title(),
+                locale.log_transferred(
+                    &from.mention().to_string(),
+                    &to.mention().to_string(),
+                    &usernames
+                        .iter()
+                        .map(|username| user_link(username))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                Color::Success,
+            ),
+            Self::Unlinked { id, username } => (
+                locale.log_unlinked_title(),
+                locale.log_unlinked(&id.mention().to_string(), &user_link(username)),
+                Color::Error,
+            ),
+        };
+
+        let timestamp = Timestamp::from_secs(OffsetDateTime::now_utc().unix_timestamp())?;
+
+        let mut embed = EmbedBuilder::new()
+            .title(title)
+            .description(description)
+            .color(color.into())
+            .timestamp(timestamp);
+
+        if let Self::Linked { linked_at, via, .. } = self {
+            embed = embed
+                .field(
+                    EmbedFieldBuilder::new(locale.log_linked_at(), embeds::timestamp(*linked_at))
+                        .inline(),
+                )
+                .field(
+                    EmbedFieldBuilder::new(locale.log_verified_via(), via.describe(locale))
+                        .inline(),
+                );
+        }
+
+        Ok(embed.validate()?.build())
+    }
+}
+
+/// Posts the event in the background if the server has a log channel.
+///
+/// Failures are only logged, so they never affect the interaction.
+pub fn log(state: &AppState, guild_id: Option<Id<GuildMarker>>, event: Event) {
+    let Some(guild_id) = guild_id else {
+        return;
+    };
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = deliver(&state, guild_id, &event).await {
+            error!(?event, "failed to log event in {guild_id}: {err}");
+        }
+    });
+}
+
+async fn deliver(state: &AppState, guild_id: Id<GuildMarker>, event: &Event) -> anyhow::Result<()> {
+    let Some(config) = state.guild_config(Some(guild_id)).await? else {
+        return Ok(());
+    };
+
+    let Some(channel_id) = config.log_channel_id else {
+        return Ok(());
+    };
+
+    let embed = event.to_embed(config.locale.into())?;
+
+    let mut attempt = 1;
+    loop {
+        let result = state
+            .discord_client
+            .create_message(channel_id)
+            .embeds(&[embed.clone()])?
+            .allowed_mentions(Some(&AllowedMentions::default()))
+            .await;
+
+        match result {
+            Ok(_) => {
+                debug!(?event, "logged event in {guild_id}");
+                return Ok(());
+            }
+            Err(err) if attempt < ATTEMPTS && is_retryable(&err) => {
+                warn!("attempt {attempt} to log event in {guild_id} failed: {err}");
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Missing permissions or a deleted channel won't fix themselves.
+fn is_retryable(err: &twilight_http::Error) -> bool {
+    match err.kind() {
+        ErrorType::Response { status, .. } => status.is_server_error(),
+        _ => true,
+    }
+}
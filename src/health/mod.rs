@@ -0,0 +1,235 @@
+//! Endpoints for monitoring: liveness, readiness and diagnostics.
+
+use std::time::Instant;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use hyper::{header, HeaderMap, StatusCode};
+use reqwest::Client;
+use serde::Serialize;
+use time::{Duration, OffsetDateTime};
+use tracing::warn;
+
+use crate::{
+    database::{Database, MIGRATOR},
+    linked_roles::UpdaterProgress,
+    state::{AppState, Config},
+};
+
+/// The updater beats at least every minute, so this leaves room for a few slow requests.
+const HEARTBEAT_TIMEOUT: Duration = Duration::minutes(5);
+
+const UPSTREAM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Endpoints which don't need authentication, just to see if the service responds.
+const UPSTREAMS: [(&str, &str); 3] = [
+    ("scratch_api", "https://api.scratch.mit.edu/health"),
+    ("scratch_db", "https://scratchdb.lefty.one/v3/"),
+    ("discord", "https://discord.com/api/v10/gateway"),
+];
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/diag", get(diag))
+}
+
+async fn healthz() -> &'static str {
+    "OK"
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Readiness {
+    database: bool,
+    migrations: bool,
+    updater: bool,
+}
+
+impl Readiness {
+    fn ready(&self) -> bool {
+        self.database && self.migrations && self.updater
+    }
+}
+
+async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let applied = state.pool.get_applied_migrations().await;
+
+    if let Err(err) = &applied {
+        warn!("readiness check failed to query the database: {err}");
+    }
+
+    let readiness = Readiness {
+        database: applied.is_ok(),
+        migrations: applied.map_or(false, |applied| migrations_applied(&applied)),
+        updater: heartbeat_fresh(&state.updater.progress(), OffsetDateTime::now_utc()),
+    };
+
+    let status = if readiness.ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(readiness))
+}
+
+fn migrations_applied(applied: &[i64]) -> bool {
+    MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .all(|migration| applied.contains(&migration.version))
+}
+
+fn heartbeat_fresh(progress: &UpdaterProgress, now: OffsetDateTime) -> bool {
+    progress
+        .heartbeat
+        .map_or(false, |heartbeat| now - heartbeat < HEARTBEAT_TIMEOUT)
+}
+
+#[derive(Debug, Serialize)]
+struct Diagnostics {
+    upstreams: Vec<Upstream>,
+    pool: PoolStats,
+    updater: UpdaterProgress,
+}
+
+#[derive(Debug, Serialize)]
+struct Upstream {
+    name: &'static str,
+    /// Responded without a server error
+    reachable: bool,
+    status: Option<u16>,
+    latency_ms: u64,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PoolStats {
+    size: u32,
+    idle: usize,
+    max: u32,
+}
+
+async fn diag(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if state.config.admin_token.is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    if !authorized(&state.config, &headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let mut upstreams = Vec::new();
+    for (name, url) in UPSTREAMS {
+        upstreams.push(check_upstream(&state.reqwest_client, name, url).await);
+    }
+
+    let pool = PoolStats {
+        size: state.pool.size(),
+        idle: state.pool.num_idle(),
+        max: state.pool.options().get_max_connections(),
+    };
+
+    Json(Diagnostics {
+        upstreams,
+        pool,
+        updater: state.updater.progress(),
+    })
+    .into_response()
+}
+
+fn authorized(config: &Config, headers: &HeaderMap) -> bool {
+    let Some(expected) = &config.admin_token else {
+        return false;
+    };
+
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or(false, |token| constant_time_eq(token, expected))
+}
+
+/// Doesn't stop at the first different byte, so the timing doesn't leak the token.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn check_upstream(client: &Client, name: &'static str, url: &str) -> Upstream {
+    let start = Instant::now();
+    let result = client.get(url).timeout(UPSTREAM_TIMEOUT).send().await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(response) => Upstream {
+            name,
+            reachable: !response.status().is_server_error(),
+            status: Some(response.status().as_u16()),
+            latency_ms,
+            error: None,
+        },
+        Err(err) => Upstream {
+            name,
+            reachable: false,
+            status: None,
+            latency_ms,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn heartbeat() {
+        let now = datetime!(2023-09-25 12:00:00 UTC);
+        let progress = |heartbeat| UpdaterProgress {
+            heartbeat,
+            ..Default::default()
+        };
+
+        assert!(heartbeat_fresh(
+            &progress(Some(datetime!(2023-09-25 11:59:00 UTC))),
+            now
+        ));
+        assert!(!heartbeat_fresh(
+            &progress(Some(datetime!(2023-09-25 11:50:00 UTC))),
+            now
+        ));
+        assert!(!heartbeat_fresh(&progress(None), now));
+    }
+
+    #[test]
+    fn migrations() {
+        let all: Vec<_> = MIGRATOR.iter().map(|migration| migration.version).collect();
+
+        let without_first: Vec<_> = all
+            .iter()
+            .copied()
+            .filter(|&version| version != all[0])
+            .collect();
+
+        assert!(migrations_applied(&all));
+        assert!(!migrations_applied(&without_first));
+    }
+
+    #[test]
+    fn compare_tokens() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+    }
+}
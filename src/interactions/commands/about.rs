@@ -1,29 +1,50 @@
+use async_trait::async_trait;
 use twilight_model::{
     application::command::{Command, CommandType},
     http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
 };
 use twilight_util::builder::command::CommandBuilder;
 
-use crate::interactions::InteractionError;
+use crate::{
+    interactions::{
+        commands::SlashCommand, context::ApplicationCommandInteraction, InteractionError,
+    },
+    locales::Locale,
+    state::AppState,
+};
 
-pub fn register() -> Command {
-    CommandBuilder::new(
-        "about",
-        "General info about the bot",
-        CommandType::ChatInput,
-    )
-    .description_localizations(vec![("pl", "Ogólne informacje o bocie")])
-    .validate()
-    .unwrap()
-    .build()
-}
+pub struct AboutCommand;
+
+#[async_trait]
+impl SlashCommand for AboutCommand {
+    fn name(&self) -> &'static str {
+        "about"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new(
+            "about",
+            "General info about the bot",
+            CommandType::ChatInput,
+        )
+        .description_localizations(vec![("pl", "Ogólne informacje o bocie")])
+        .validate()
+        .unwrap()
+        .build()
+    }
 
-pub async fn run() -> Result<InteractionResponse, InteractionError> {
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(InteractionResponseData {
-            content: Some("About Scratchy".into()),
-            ..Default::default()
-        }),
-    })
+    async fn run(
+        &self,
+        _state: AppState,
+        _interaction: ApplicationCommandInteraction,
+        _locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                content: Some("About Scratchy".into()),
+                ..Default::default()
+            }),
+        })
+    }
 }
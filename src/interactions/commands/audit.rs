@@ -0,0 +1,263 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use tracing::error;
+use twilight_mention::Mention;
+use twilight_model::{
+    application::command::{Command, CommandType},
+    channel::message::{component::ActionRow, AllowedMentions, Component, MessageFlags},
+    guild::Permissions,
+    http::{
+        attachment::Attachment,
+        interaction::{InteractionResponse, InteractionResponseType},
+    },
+    id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::{
+    command::{BooleanBuilder, CommandBuilder},
+    InteractionResponseDataBuilder,
+};
+
+use crate::{
+    audit::{audit, Audit},
+    interactions::{
+        commands::SlashCommand,
+        components::audit::{self as audit_component, CustomId},
+        context::{ApplicationCommandInteraction, GetOption},
+        InteractionError,
+    },
+    locales::Locale,
+    state::AppState,
+};
+
+const MEMBERS_PER_PAGE: usize = 20;
+
+pub struct AuditCommand;
+
+#[async_trait]
+impl SlashCommand for AuditCommand {
+    fn name(&self) -> &'static str {
+        "audit"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new(
+            "audit",
+            "List members without a linked Scratch account",
+            CommandType::ChatInput,
+        )
+        .description_localizations(vec![(
+            "pl",
+            "Wyświetl członków bez połączonego konta Scratch",
+        )])
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .option(
+            BooleanBuilder::new("csv", "Attach all members as a CSV file")
+                .description_localizations(vec![(
+                    "pl",
+                    "Załącz wszystkich członków jako plik CSV",
+                )]),
+        )
+        .validate()
+        .unwrap()
+        .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        // Safe to unwrap because the command is disabled in DMs
+        let guild_id = interaction.guild_id.unwrap();
+        let csv: Option<&bool> = interaction.data().options.get_optional("csv")?;
+        let csv = csv.copied().unwrap_or(false);
+
+        // Listing members of a big server takes longer than Discord waits for a response
+        let token = interaction.token.to_owned();
+        tokio::spawn(async move {
+            respond(&state, &token, guild_id, 0, csv, locale).await;
+        });
+
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: Some(
+                InteractionResponseDataBuilder::new()
+                    .flags(MessageFlags::EPHEMERAL)
+                    .build(),
+            ),
+        })
+    }
+}
+
+/// Runs the audit and edits the response of the interaction with the requested page.
+pub async fn respond(
+    state: &AppState,
+    token: &str,
+    guild_id: Id<GuildMarker>,
+    page: usize,
+    csv: bool,
+    locale: Locale,
+) {
+    let result = async {
+        let audit = audit(state, guild_id).await?;
+        let (content, components) = render(&audit, page, locale);
+
+        let attachments = if csv {
+            vec![Attachment::from_bytes(
+                format!("audit-{guild_id}.csv"),
+                audit.to_csv().into_bytes(),
+                0,
+            )]
+        } else {
+            Vec::new()
+        };
+
+        let client = state
+            .discord_client
+            .interaction(state.config.client_id.parse()?);
+
+        let mut request = client
+            .update_response(token)
+            .content(Some(&content))?
+            .components(Some(&components))?
+            .allowed_mentions(Some(&AllowedMentions::default()));
+
+        // Changing pages keeps the file from the first response
+        if !attachments.is_empty() {
+            request = request.attachments(&attachments)?;
+        }
+
+        request.await?;
+
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(err) = result {
+        error!("audit of {guild_id} failed: {err}");
+
+        let result = async {
+            state
+                .discord_client
+                .interaction(state.config.client_id.parse()?)
+                .update_response(token)
+                .content(Some(&locale.audit_failed()))?
+                .await?;
+
+            anyhow::Ok(())
+        }
+        .await;
+
+        if let Err(err) = result {
+            error!("{}", err);
+        }
+    }
+}
+
+fn render(audit: &Audit, page: usize, locale: Locale) -> (String, Vec<Component>) {
+    let unlinked = audit.unlinked();
+
+    let mut content = locale.audit_summary(
+        &audit.linked().to_string(),
+        &audit.members.len().to_string(),
+        &unlinked.len().to_string(),
+    );
+
+    if unlinked.is_empty() {
+        content.push('\n');
+        content.push_str(&locale.audit_all_linked());
+        return (content, Vec::new());
+    }
+
+    let pages = (unlinked.len() + MEMBERS_PER_PAGE - 1) / MEMBERS_PER_PAGE;
+    let page = page.min(pages - 1);
+
+    content.push_str("\n\n");
+    content.push_str(&locale.audit_unlinked_page(&(page + 1).to_string(), &pages.to_string()));
+
+    for member in unlinked
+        .iter()
+        .skip(page * MEMBERS_PER_PAGE)
+        .take(MEMBERS_PER_PAGE)
+    {
+        write!(content, "\n- {} {}", member.id.mention(), member.name).unwrap();
+    }
+
+    if pages == 1 {
+        return (content, Vec::new());
+    }
+
+    let components = vec![Component::ActionRow(ActionRow {
+        components: vec![
+            audit_component::build(
+                CustomId {
+                    page: page.saturating_sub(1),
+                },
+                locale.previous_page(),
+                page == 0,
+            ),
+            audit_component::build(
+                CustomId { page: page + 1 },
+                locale.next_page(),
+                page + 1 == pages,
+            ),
+        ],
+    })];
+
+    (content, components)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audit::AuditedMember;
+
+    use super::*;
+
+    fn audit(unlinked: u64) -> Audit {
+        let mut members = vec![AuditedMember {
+            id: Id::new(1),
+            name: "linked".into(),
+            usernames: vec!["PMJ_Studio".into()],
+        }];
+
+        members.extend((0..unlinked).map(|i| AuditedMember {
+            id: Id::new(i + 2),
+            name: format!("unlinked{i}"),
+            usernames: vec![],
+        }));
+
+        Audit { members }
+    }
+
+    #[test]
+    fn all_linked() {
+        let (content, components) = render(&audit(0), 0, Locale::En);
+
+        assert!(!content.contains("- "));
+        assert!(components.is_empty());
+    }
+
+    #[test]
+    fn one_page() {
+        let (content, components) = render(&audit(3), 0, Locale::En);
+
+        assert_eq!(content.matches("\n- ").count(), 3);
+        assert!(components.is_empty());
+    }
+
+    #[test]
+    fn last_page() {
+        let (content, components) = render(&audit(45), 5, Locale::En);
+
+        assert_eq!(
+            content.matches("\n- ").count(),
+            5,
+            "clamped to the last page"
+        );
+        assert!(content.contains("unlinked44"));
+        assert_eq!(components.len(), 1);
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use twilight_mention::Mention;
+use twilight_model::{
+    application::command::{Command, CommandType},
+    channel::{message::MessageFlags, ChannelType},
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::ChannelMarker, Id},
+};
+use twilight_util::builder::{
+    command::{BooleanBuilder, ChannelBuilder, CommandBuilder, StringBuilder, SubCommandBuilder},
+    InteractionResponseDataBuilder,
+};
+
+use crate::{
+    database::{Database, GuildConfig},
+    interactions::{
+        commands::{SlashCommand, COMMANDS},
+        context::{
+            command_options, ApplicationCommandInteraction, FromOptions, GetOption, GetSubcommand,
+        },
+        InteractionError,
+    },
+    locales::Locale,
+    state::AppState,
+};
+
+/// Commands which can be disabled, everything except `/config`.
+fn disableable_commands() -> impl Iterator<Item = &'static str> {
+    COMMANDS
+        .iter()
+        .map(|command| command.name())
+        .filter(|&name| name != "config")
+}
+
+command_options! {
+    struct CommandOptions {
+        command: String,
+        enabled: bool,
+    }
+}
+
+pub struct ConfigCommand;
+
+#[async_trait]
+impl SlashCommand for ConfigCommand {
+    fn name(&self) -> &'static str {
+        "config"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new("config", "Server settings", CommandType::ChatInput)
+            .description_localizations(vec![("pl", "Ustawienia serwera")])
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .dm_permission(false)
+            .option(
+                SubCommandBuilder::new("show", "Show the current settings")
+                    .description_localizations(vec![("pl", "Wyświetl obecne ustawienia")]),
+            )
+            .option(
+                SubCommandBuilder::new("log-channel", "Set the channel for event logs")
+                    .description_localizations(vec![("pl", "Ustaw kanał dziennika zdarzeń")])
+                    .option(
+                        ChannelBuilder::new("channel", "Leave empty to disable logging")
+                            .channel_types([ChannelType::GuildText])
+                            .description_localizations(vec![(
+                                "pl",
+                                "Zostaw puste, aby wyłączyć dziennik",
+                            )]),
+                    ),
+            )
+            .option(
+                SubCommandBuilder::new("visibility", "Set who can see command responses")
+                    .description_localizations(vec![(
+                        "pl",
+                        "Ustaw, kto widzi odpowiedzi na komendy",
+                    )])
+                    .option(
+                        StringBuilder::new("visibility", "Visibility")
+                            .required(true)
+                            .description_localizations(vec![("pl", "Widoczność")])
+                            .choices([("Everyone", "public"), ("Only the user", "ephemeral")]),
+                    ),
+            )
+            .option(
+                SubCommandBuilder::new("locale", "Set the language of responses")
+                    .description_localizations(vec![("pl", "Ustaw język odpowiedzi")])
+                    .option(
+                        StringBuilder::new("locale", "Language")
+                            .required(true)
+                            .description_localizations(vec![("pl", "Język")])
+                            .choices([
+                                ("User's language", "user"),
+                                ("English", "en"),
+                                ("Polski", "pl"),
+                            ]),
+                    ),
+            )
+            .option(
+                SubCommandBuilder::new("nicknames", "Set nicknames to Scratch usernames")
+                    .description_localizations(vec![(
+                        "pl",
+                        "Ustawiaj pseudonimy na nazwy użytkowników Scratch",
+                    )])
+                    .option(
+                        BooleanBuilder::new("enabled", "Enabled")
+                            .required(true)
+                            .description_localizations(vec![("pl", "Włączone")]),
+                    ),
+            )
+            .option(
+                SubCommandBuilder::new("command", "Enable or disable a command")
+                    .description_localizations(vec![("pl", "Włącz lub wyłącz komendę")])
+                    .option(
+                        StringBuilder::new("command", "Command")
+                            .required(true)
+                            .description_localizations(vec![("pl", "Komenda")])
+                            .choices(disableable_commands().map(|command| (command, command))),
+                    )
+                    .option(
+                        BooleanBuilder::new("enabled", "Enabled")
+                            .required(true)
+                            .description_localizations(vec![("pl", "Włączona")]),
+                    ),
+            )
+            .validate()
+            .unwrap()
+            .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        // Safe to unwrap because the command is disabled in DMs
+        let mut config = state.guild_config(interaction.guild_id).await?.unwrap();
+
+        let (subcommand, options) = interaction.data().options.get_subcommand()?;
+
+        match subcommand {
+            "show" => {}
+            "log-channel" => {
+                let channel_id: Option<&Id<ChannelMarker>> = options.get_optional("channel")?;
+                config.log_channel_id = channel_id.copied();
+            }
+            "visibility" => {
+                let visibility: &String = options.get_option("visibility")?;
+                config.ephemeral = visibility == "ephemeral";
+            }
+            "locale" => {
+                let value: &String = options.get_option("locale")?;
+                config.locale = match value.as_str() {
+                    "user" => None,
+                    value => Some(value.to_string()),
+                };
+            }
+            "nicknames" => {
+                let enabled: &bool = options.get_option("enabled")?;
+                config.sync_nicknames = *enabled;
+            }
+            "command" => {
+                let CommandOptions { command, enabled } = CommandOptions::from_options(options)?;
+
+                config.disabled_commands.retain(|name| *name != command);
+                if !enabled {
+                    config.disabled_commands.push(command);
+                    config.disabled_commands.sort();
+                }
+            }
+            _ => panic!("unknown subcommand name"),
+        }
+
+        if subcommand != "show" {
+            config = state.pool.write_guild_config(&config).await?;
+        }
+
+        // Show the new settings in the new language
+        let locale = match &config.locale {
+            Some(value) => Some(value.to_owned()).into(),
+            None => locale,
+        };
+
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(
+                InteractionResponseDataBuilder::new()
+                    .content(describe(&config, locale))
+                    .allowed_mentions(Default::default())
+                    .flags(MessageFlags::EPHEMERAL)
+                    .build(),
+            ),
+        })
+    }
+}
+
+fn describe(config: &GuildConfig, locale: Locale) -> String {
+    let mut content = locale.config_title();
+
+    let log_channel = match config.log_channel_id {
+        Some(id) => id.mention().to_string(),
+        None => locale.config_none(),
+    };
+    write!(content, "\n- {}", locale.config_log_channel(&log_channel)).unwrap();
+
+    let visibility = if config.ephemeral {
+        locale.config_ephemeral()
+    } else {
+        locale.config_public()
+    };
+    write!(content, "\n- {}", locale.config_visibility(&visibility)).unwrap();
+
+    let language = match config.locale.as_deref() {
+        Some("en") => "English".to_string(),
+        Some("pl") => "Polski".to_string(),
+        Some(other) => other.to_string(),
+        None => locale.config_user_locale(),
+    };
+    write!(content, "\n- {}", locale.config_locale(&language)).unwrap();
+
+    let nicknames = if config.sync_nicknames {
+        locale.config_enabled()
+    } else {
+        locale.config_disabled()
+    };
+    write!(content, "\n- {}", locale.config_nicknames(&nicknames)).unwrap();
+
+    let disabled = if config.disabled_commands.is_empty() {
+        locale.config_none()
+    } else {
+        config
+            .disabled_commands
+            .iter()
+            .map(|command| format!("`/{command}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    write!(
+        content,
+        "\n- {}",
+        locale.config_disabled_commands(&disabled)
+    )
+    .unwrap();
+
+    content
+}
@@ -1,9 +1,11 @@
 use std::fmt::Write;
 
+use async_trait::async_trait;
 use twilight_mention::Mention;
 use twilight_model::{
     application::command::{Command, CommandType},
     http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::UserMarker, Id},
 };
 use twilight_util::builder::{
     command::{CommandBuilder, StringBuilder, SubCommandBuilder, UserBuilder},
@@ -11,8 +13,10 @@ use twilight_util::builder::{
 };
 
 use crate::{
-    database::Database,
+    database::{Database, Transactional},
+    embeds::timestamp,
     interactions::{
+        commands::SlashCommand,
         context::{ApplicationCommandInteraction, GetOption, GetSubcommand},
         InteractionError,
     },
@@ -21,102 +25,167 @@ use crate::{
     state::AppState,
 };
 
-pub fn register() -> Command {
-    CommandBuilder::new("find", "Find linked accounts", CommandType::ChatInput)
-        .description_localizations(vec![("pl", "Znajdź połączone konta")])
-        .option(
-            SubCommandBuilder::new("by-scratch", "Find linked accounts of a Scratch user").option(
-                StringBuilder::new("username", "Scratch account URL or username")
-                    .required(true)
-                    .description_localizations(vec![(
-                        "pl",
-                        "Link do konta Scratch lub nazwa użytkownika",
-                    )]),
-            ),
-        )
-        .option(
-            SubCommandBuilder::new("by-discord", "Find linked accounts of a Discord user").option(
-                UserBuilder::new("user", "Discord account")
-                    .required(true)
-                    .description_localizations(vec![("pl", "Konto Discord")]),
-            ),
-        )
-        .validate()
-        .unwrap()
-        .build()
-}
+pub struct FindCommand;
 
-pub async fn run(
-    state: AppState,
-    interaction: ApplicationCommandInteraction,
-    locale: Locale,
-) -> Result<InteractionResponse, InteractionError> {
-    let (subcommand, options) = interaction.data().options.get_subcommand()?;
-
-    let id = match subcommand {
-        "by-scratch" => {
-            let username: &String = options.get_option("username")?;
-
-            let Some(username) = extract_username(username) else {
-                return Ok(InteractionResponse {
-                    kind: InteractionResponseType::ChannelMessageWithSource,
-                    data: Some(
-                        InteractionResponseDataBuilder::new()
-                            .content(locale.invalid_username())
-                            .build(),
+#[async_trait]
+impl SlashCommand for FindCommand {
+    fn name(&self) -> &'static str {
+        "find"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new("find", "Find linked accounts", CommandType::ChatInput)
+            .description_localizations(vec![("pl", "Znajdź połączone konta")])
+            .option(
+                SubCommandBuilder::new("by-scratch", "Find linked accounts of a Scratch user")
+                    .option(
+                        StringBuilder::new("username", "Scratch account URL or username")
+                            .required(true)
+                            .description_localizations(vec![(
+                                "pl",
+                                "Link do konta Scratch lub nazwa użytkownika",
+                            )]),
                     ),
-                });
-            };
-
-            if let Some(scratch_account) =
-                state.pool.get_scratch_account(username.to_string()).await?
-            {
-                scratch_account.id
-            } else {
-                return Ok(InteractionResponse {
-                    kind: InteractionResponseType::ChannelMessageWithSource,
-                    data: Some(
-                        InteractionResponseDataBuilder::new()
-                            .content(locale.no_linked_discord_account(&user_link(&username)))
-                            .build(),
+            )
+            .option(
+                SubCommandBuilder::new("by-discord", "Find linked accounts of a Discord user")
+                    .option(
+                        UserBuilder::new("user", "Discord account")
+                            .required(true)
+                            .description_localizations(vec![("pl", "Konto Discord")]),
                     ),
-                });
-            }
-        }
-        "by-discord" => *options.get_option("user")?,
-        _ => panic!("unknown subcommand name"),
-    };
+            )
+            .validate()
+            .unwrap()
+            .build()
+    }
 
-    let mention = id.mention().to_string();
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        let (subcommand, options) = interaction.data().options.get_subcommand()?;
+
+        let id = match subcommand {
+            "by-scratch" => {
+                let username: &String = options.get_option("username")?;
 
-    let linked_accounts = state.pool.get_linked_scratch_accounts(id).await?;
+                let Some(username) = extract_username(username) else {
+                    return Ok(InteractionResponse {
+                        kind: InteractionResponseType::ChannelMessageWithSource,
+                        data: Some(
+                            InteractionResponseDataBuilder::new()
+                                .content(locale.invalid_username())
+                                .build(),
+                        ),
+                    });
+                };
+
+                if let Some(scratch_account) = state
+                    .pool
+                    .get_scratch_account(username.to_string(), None)
+                    .await?
+                {
+                    scratch_account.id
+                } else {
+                    return Ok(InteractionResponse {
+                        kind: InteractionResponseType::ChannelMessageWithSource,
+                        data: Some(
+                            InteractionResponseDataBuilder::new()
+                                .content(locale.no_linked_discord_account(&user_link(&username)))
+                                .build(),
+                        ),
+                    });
+                }
+            }
+            "by-discord" => *options.get_option("user")?,
+            _ => panic!("unknown subcommand name"),
+        };
+
+        let content = describe_linked_accounts(&state.pool, id, locale).await?;
 
-    if linked_accounts.len() == 0 {
         return Ok(InteractionResponse {
             kind: InteractionResponseType::ChannelMessageWithSource,
             data: Some(
                 InteractionResponseDataBuilder::new()
-                    .content(locale.no_linked_scratch_accounts(&mention))
+                    .content(content)
                     .allowed_mentions(Default::default())
                     .build(),
             ),
         });
     }
+}
+
+/// Lists the Scratch accounts linked to the Discord account, with gone accounts crossed out.
+async fn describe_linked_accounts<P>(
+    pool: &P,
+    id: Id<UserMarker>,
+    locale: Locale,
+) -> Result<String, sqlx::Error>
+where
+    P: Transactional,
+    for<'a> &'a P: Database<Error = sqlx::Error>,
+{
+    let mention = id.mention().to_string();
+
+    let linked_accounts = pool.get_linked_scratch_accounts(id).await?;
+
+    if linked_accounts.is_empty() {
+        return Ok(locale.no_linked_scratch_accounts(&mention));
+    }
 
     let mut content = locale.linked_accounts(&mention);
 
     for account in linked_accounts {
-        content.write_str("\n- ").unwrap();
-        content.write_str(&user_link(&account.username)).unwrap();
+        let user = user_link(&account.username);
+        let line = match (account.linked_at, account.verified_via) {
+            (Some(linked_at), Some(via)) => {
+                locale.linked_account_details(&via.describe(locale), &timestamp(linked_at), &user)
+            }
+            _ => user,
+        };
+        if account.gone_at.is_some() {
+            write!(content, "\n- ~~{line}~~ {}", locale.account_gone_mark()).unwrap();
+        } else {
+            write!(content, "\n- {line}").unwrap();
+        }
     }
 
-    return Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(
-            InteractionResponseDataBuilder::new()
-                .content(content)
-                .allowed_mentions(Default::default())
-                .build(),
-        ),
-    });
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database::{link_account, MemoryDatabase},
+        verification::VerifiedVia,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn linked_accounts() {
+        let pool = MemoryDatabase::default();
+        let id = "755497867606622450".parse().unwrap();
+
+        let content = describe_linked_accounts(&pool, id, Locale::En)
+            .await
+            .unwrap();
+
+        assert!(content.contains("doesn't have any"), "{content}");
+
+        link_account(&pool, "PMJ_Studio".into(), None, id, VerifiedVia::Comment)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let content = describe_linked_accounts(&pool, id, Locale::En)
+            .await
+            .unwrap();
+
+        assert!(content.contains("PMJ_Studio"), "{content}");
+        assert!(content.contains("via"), "{content}");
+    }
 }
@@ -1,10 +1,8 @@
+use async_trait::async_trait;
 use twilight_mention::Mention;
 use twilight_model::{
     application::command::{Command, CommandType},
-    channel::message::{
-        component::{ActionRow, Button, ButtonStyle},
-        Component,
-    },
+    channel::message::{component::ActionRow, Component, MessageFlags},
     http::interaction::{InteractionResponse, InteractionResponseType},
 };
 use twilight_util::builder::{
@@ -15,6 +13,7 @@ use twilight_util::builder::{
 use crate::{
     database::Database,
     interactions::{
+        commands::SlashCommand,
         components::code::{self, CustomId},
         context::{ApplicationCommandInteraction, GetOption},
         InteractionError,
@@ -23,115 +22,146 @@ use crate::{
     scratch::{
         api::ScratchAPIClient,
         site::{extract_username, user_link},
-        STUDIO_URL,
     },
     state::AppState,
+    verification::Method,
 };
 
-pub fn register() -> Command {
-    CommandBuilder::new("link", "Link your Scratch account", CommandType::ChatInput)
-        .description_localizations(vec![("pl", "Połącz swoje konto Scratch")])
-        .option(
-            StringBuilder::new("username", "Account URL or username")
-                .required(true)
-                .description_localizations(vec![("pl", "Link do konta lub nazwa użytkownika")]),
-        )
-        .validate()
-        .unwrap()
-        .build()
-}
+pub struct LinkCommand;
 
-pub async fn run(
-    state: AppState,
-    interaction: ApplicationCommandInteraction,
-    locale: Locale,
-) -> Result<InteractionResponse, InteractionError> {
-    let username: &String = interaction.data().options.get_option("username")?;
+#[async_trait]
+impl SlashCommand for LinkCommand {
+    fn name(&self) -> &'static str {
+        "link"
+    }
 
-    let Some(mut username) = extract_username(username) else {
-        return Ok(InteractionResponse {
-            kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(locale.invalid_username())
-                    .build(),
-            ),
-        });
-    };
+    fn register(&self) -> Command {
+        CommandBuilder::new("link", "Link your Scratch account", CommandType::ChatInput)
+            .description_localizations(vec![("pl", "Połącz swoje konto Scratch")])
+            .option(
+                StringBuilder::new("username", "Account URL or username")
+                    .required(true)
+                    .description_localizations(vec![("pl", "Link do konta lub nazwa użytkownika")]),
+            )
+            .validate()
+            .unwrap()
+            .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        let username: &String = interaction.data().options.get_option("username")?;
+
+        let Some(mut username) = extract_username(username) else {
+            return Ok(InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .content(locale.invalid_username())
+                        .build(),
+                ),
+            });
+        };
 
-    let author_id = interaction.author_id().unwrap();
+        let author_id = interaction.author_id().unwrap();
 
-    let (db, scratch_api) = tokio::join!(
-        state.pool.get_scratch_account(username.to_string()),
-        state.reqwest_client.get_scratch_api_user(&username),
-    );
+        let (db, scratch_api) = tokio::join!(
+            state.pool.get_scratch_account(username.to_string(), None),
+            state.reqwest_client.get_scratch_api_user(&username),
+        );
 
-    if let Some(account) = db? {
-        username = account.username;
+        // A link with another Scratch ID belongs to a deleted account with the same name
+        let scratch_id = match &scratch_api {
+            Ok(Some(user)) => Some(user.id),
+            _ => None,
+        };
+        let db = db?.filter(|account| match (account.scratch_id, scratch_id) {
+            (Some(linked), Some(current)) => linked == current,
+            _ => true,
+        });
+
+        // Verifying an account linked to someone else transfers it instead
+        let linked_to_other = match db {
+            Some(account) if account.id == author_id => {
+                return Ok(InteractionResponse {
+                    kind: InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(
+                        InteractionResponseDataBuilder::new()
+                            .content(locale.already_linked_to_you(&user_link(&account.username)))
+                            .allowed_mentions(Default::default())
+                            .build(),
+                    ),
+                });
+            }
+            Some(account) => Some(account.id),
+            None => None,
+        };
+
+        let scratch_api = scratch_api?;
+        match scratch_api {
+            Some(user) => username = user.username,
+            None => {
+                return Ok(InteractionResponse {
+                    kind: InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(
+                        InteractionResponseDataBuilder::new()
+                            .content(locale.user_not_found(&user_link(&username)))
+                            .build(),
+                    ),
+                })
+            }
+        }
 
-        let content = if account.id == author_id {
-            locale.already_linked_to_you(&user_link(&username))
-        } else {
-            locale.already_linked_to_other(&account.id.mention().to_string(), &user_link(&username))
+        let mut content = match linked_to_other {
+            Some(id) => {
+                locale.link_transfer(&id.mention().to_string(), &user_link(&username)) + "\n"
+            }
+            None => String::new(),
         };
+        content.push_str(
+            &locale.link_your_account(&author_id.mention().to_string(), &user_link(&username)),
+        );
+
+        let mut components = vec![code::build(
+            CustomId {
+                username: username.to_string(),
+                id: author_id,
+                method: Method::Comment,
+                scratch_id,
+            },
+            locale,
+        )];
+
+        if state.config.cloud_project_id.is_some() {
+            content.push('\n');
+            content.push_str(&locale.link_your_account_cloud());
+
+            components.push(code::build(
+                CustomId {
+                    username: username.to_string(),
+                    id: author_id,
+                    method: Method::Cloud,
+                    scratch_id,
+                },
+                locale,
+            ));
+        }
 
         return Ok(InteractionResponse {
             kind: InteractionResponseType::ChannelMessageWithSource,
             data: Some(
                 InteractionResponseDataBuilder::new()
                     .content(content)
+                    .components([Component::ActionRow(ActionRow { components })])
                     .allowed_mentions(Default::default())
+                    // The code replaces this message, so only the author should see it
+                    .flags(MessageFlags::EPHEMERAL)
                     .build(),
             ),
         });
     }
-
-    let scratch_api = scratch_api?;
-    match scratch_api {
-        Some(user) => username = user.username,
-        None => {
-            return Ok(InteractionResponse {
-                kind: InteractionResponseType::ChannelMessageWithSource,
-                data: Some(
-                    InteractionResponseDataBuilder::new()
-                        .content(locale.user_not_found(&user_link(&username)))
-                        .build(),
-                ),
-            })
-        }
-    }
-
-    let code_button = code::build(
-        CustomId {
-            username: username.to_string(),
-            id: author_id,
-        },
-        locale,
-    );
-
-    return Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(
-            InteractionResponseDataBuilder::new()
-                .content(
-                    locale
-                        .link_your_account(&author_id.mention().to_string(), &user_link(&username)),
-                )
-                .components([Component::ActionRow(ActionRow {
-                    components: vec![
-                        code_button,
-                        Component::Button(Button {
-                            custom_id: None,
-                            disabled: false,
-                            emoji: None,
-                            label: Some(locale.go_to_studio()),
-                            style: ButtonStyle::Link,
-                            url: Some(STUDIO_URL.into()),
-                        }),
-                    ],
-                })])
-                .allowed_mentions(Default::default())
-                .build(),
-        ),
-    });
 }
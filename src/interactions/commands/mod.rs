@@ -1,21 +1,85 @@
+use std::time::Instant;
+
+use async_trait::async_trait;
 use tracing::{debug_span, Instrument};
-use twilight_model::http::interaction::InteractionResponse;
+use twilight_model::{
+    application::{command::Command, interaction::InteractionType},
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::{locales::Locale, state::AppState};
+use crate::{database::GuildConfig, locales::Locale, metrics::metrics, state::AppState};
 
 use super::{context::ApplicationCommandInteraction, InteractionError};
 
 pub mod about;
+pub mod audit;
+pub mod config;
 pub mod find;
 pub mod link;
+pub mod nick;
 pub mod ping;
 pub mod project;
+pub mod roles;
+pub mod stats;
 pub mod user;
 
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    /// Has to match the name in [`SlashCommand::register`].
+    fn name(&self) -> &'static str;
+
+    fn register(&self) -> Command;
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError>;
+
+    /// Only called for commands with options which have autocomplete enabled.
+    async fn autocomplete(
+        &self,
+        _state: AppState,
+        _interaction: ApplicationCommandInteraction,
+        _locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        Err(InteractionError::UnsupportedType(
+            InteractionType::ApplicationCommandAutocomplete,
+        ))
+    }
+}
+
+/// Every command, used both for registration and dispatch.
+pub static COMMANDS: &[&dyn SlashCommand] = &[
+    &about::AboutCommand,
+    &audit::AuditCommand,
+    &config::ConfigCommand,
+    &find::FindCommand,
+    &link::LinkCommand,
+    &nick::NickCommand,
+    &ping::PingCommand,
+    &project::ProjectCommand,
+    &roles::RolesCommand,
+    &stats::StatsCommand,
+    &user::UserCommand,
+];
+
+fn find_command(name: &str) -> Result<&'static dyn SlashCommand, InteractionError> {
+    COMMANDS
+        .iter()
+        .copied()
+        .find(|command| command.name() == name)
+        .ok_or_else(|| InteractionError::UnknownCommand(name.to_string()))
+}
+
 pub async fn router(
     state: AppState,
     interaction: ApplicationCommandInteraction,
     locale: Locale,
+    guild_config: Option<GuildConfig>,
 ) -> Result<InteractionResponse, InteractionError> {
     let span = debug_span!(
         "command",
@@ -25,17 +89,107 @@ pub async fn router(
         channel = ?interaction.channel_id.map(|v| v.get()),
     );
 
-    async move {
-        match interaction.data().name.as_str() {
-            "about" => about::run().await,
-            "find" => find::run(state, interaction, locale).await,
-            "link" => link::run(state, interaction, locale).await,
-            "ping" => ping::run(state, locale).await,
-            "project" => project::run(state, interaction, locale).await,
-            "user" => user::run(state, interaction, locale).await,
-            command => Err(InteractionError::UnknownCommand(command.to_string())),
+    let name = interaction.data().name.to_owned();
+
+    // `/config` can't be disabled, otherwise there would be no way to enable it again
+    if let Some(guild_config) = &guild_config {
+        if name != "config" && !guild_config.is_enabled(&name) {
+            return Ok(InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .content(locale.command_disabled())
+                        .flags(MessageFlags::EPHEMERAL)
+                        .build(),
+                ),
+            });
         }
     }
-    .instrument(span)
-    .await
+
+    let start = Instant::now();
+
+    let result = async { find_command(&name)?.run(state, interaction, locale).await }
+        .instrument(span)
+        .await;
+
+    metrics().interaction("command", &name, result.is_ok(), start.elapsed());
+
+    let mut response = result?;
+
+    if guild_config.is_some_and(|guild_config| guild_config.ephemeral) {
+        make_ephemeral(&mut response);
+    }
+
+    Ok(response)
+}
+
+pub async fn autocomplete(
+    state: AppState,
+    interaction: ApplicationCommandInteraction,
+    locale: Locale,
+) -> Result<InteractionResponse, InteractionError> {
+    let name = interaction.data().name.to_owned();
+
+    find_command(&name)?
+        .autocomplete(state, interaction, locale)
+        .await
+}
+
+/// Hides a response which would otherwise be visible to everyone in the channel.
+fn make_ephemeral(response: &mut InteractionResponse) {
+    if let InteractionResponseType::ChannelMessageWithSource
+    | InteractionResponseType::DeferredChannelMessageWithSource = response.kind
+    {
+        // Deferred responses have no data, but the flags still apply to the follow-up message
+        let data = response
+            .data
+            .get_or_insert_with(|| InteractionResponseDataBuilder::new().build());
+        data.flags = Some(data.flags.unwrap_or_else(MessageFlags::empty) | MessageFlags::EPHEMERAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn names_match() {
+        for command in COMMANDS {
+            assert_eq!(command.register().name, command.name());
+            assert!(find_command(command.name()).is_ok());
+        }
+    }
+
+    #[test]
+    fn names_unique() {
+        let names: HashSet<_> = COMMANDS.iter().map(|command| command.name()).collect();
+
+        assert_eq!(names.len(), COMMANDS.len());
+    }
+
+    #[test]
+    fn ephemeral_deferred() {
+        let mut response = InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: None,
+        };
+
+        make_ephemeral(&mut response);
+
+        assert_eq!(response.data.unwrap().flags, Some(MessageFlags::EPHEMERAL));
+    }
+
+    #[test]
+    fn ephemeral_autocomplete() {
+        let mut response = InteractionResponse {
+            kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+            data: None,
+        };
+
+        make_ephemeral(&mut response);
+
+        assert_eq!(response.data, None, "only messages");
+    }
 }
@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use twilight_mention::Mention;
+use twilight_model::{
+    application::command::{Command, CommandType},
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::{
+    command::{CommandBuilder, StringBuilder, SubCommandBuilder},
+    InteractionResponseDataBuilder,
+};
+
+use crate::{
+    database::Database,
+    interactions::{
+        commands::SlashCommand,
+        context::{ApplicationCommandInteraction, GetOption, GetSubcommand},
+        InteractionError,
+    },
+    locales::Locale,
+    nicknames::{set_nickname, NicknameError},
+    scratch::site::{extract_username, user_link},
+    state::AppState,
+};
+
+pub struct NickCommand;
+
+#[async_trait]
+impl SlashCommand for NickCommand {
+    fn name(&self) -> &'static str {
+        "nick"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new("nick", "Nickname", CommandType::ChatInput)
+            .description_localizations(vec![("pl", "Pseudonim")])
+            .dm_permission(false)
+            .option(
+                SubCommandBuilder::new("sync", "Set your nickname to your Scratch username")
+                    .description_localizations(vec![(
+                        "pl",
+                        "Ustaw swój pseudonim na nazwę użytkownika Scratch",
+                    )])
+                    .option(
+                        StringBuilder::new("username", "Linked account, if you have more than one")
+                            .description_localizations(vec![(
+                                "pl",
+                                "Połączone konto, jeśli masz więcej niż jedno",
+                            )]),
+                    ),
+            )
+            .validate()
+            .unwrap()
+            .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        let (subcommand, options) = interaction.data().options.get_subcommand()?;
+
+        let content = match subcommand {
+            "sync" => {
+                let username: Option<&String> = options.get_optional("username")?;
+                sync(&state, &interaction, username, locale).await?
+            }
+            _ => panic!("unknown subcommand name"),
+        };
+
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(
+                InteractionResponseDataBuilder::new()
+                    .content(content)
+                    .allowed_mentions(Default::default())
+                    .build(),
+            ),
+        })
+    }
+}
+
+async fn sync(
+    state: &AppState,
+    interaction: &ApplicationCommandInteraction,
+    username: Option<&String>,
+    locale: Locale,
+) -> Result<String, InteractionError> {
+    // Safe to unwrap because the command is disabled in DMs
+    let config = state.guild_config(interaction.guild_id).await?.unwrap();
+
+    if !config.sync_nicknames {
+        return Ok(locale.nickname_sync_disabled());
+    }
+
+    let author_id = interaction.author_id().unwrap();
+
+    let linked_accounts = state.pool.get_linked_scratch_accounts(author_id).await?;
+
+    let account = match username {
+        Some(username) => {
+            let Some(username) = extract_username(username) else {
+                return Ok(locale.invalid_username());
+            };
+
+            match linked_accounts
+                .into_iter()
+                .find(|account| account.username.eq_ignore_ascii_case(&username))
+            {
+                Some(account) => account,
+                None => return Ok(locale.nickname_not_linked(&user_link(&username))),
+            }
+        }
+        // The primary account, the same one set after linking
+        None => match linked_accounts.into_iter().next() {
+            Some(account) => account,
+            None => return Ok(locale.no_linked_scratch_accounts(&author_id.mention().to_string())),
+        },
+    };
+
+    match set_nickname(state, config.guild_id, author_id, &account.username).await {
+        Ok(()) => Ok(locale.nickname_updated(&account.username)),
+        Err(NicknameError::MissingPermissions) => Ok(locale.nickname_missing_permissions()),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -1,25 +1,46 @@
+use async_trait::async_trait;
 use twilight_model::{
     application::command::{Command, CommandType},
     http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
 };
 use twilight_util::builder::command::CommandBuilder;
 
-use crate::{interactions::InteractionError, locales::Locale, state::AppState};
+use crate::{
+    interactions::{
+        commands::SlashCommand, context::ApplicationCommandInteraction, InteractionError,
+    },
+    locales::Locale,
+    state::AppState,
+};
 
-pub fn register() -> Command {
-    CommandBuilder::new("ping", "Current bot status", CommandType::ChatInput)
-        .description_localizations(vec![("pl", "Aktualny stan bota")])
-        .validate()
-        .unwrap()
-        .build()
-}
+pub struct PingCommand;
+
+#[async_trait]
+impl SlashCommand for PingCommand {
+    fn name(&self) -> &'static str {
+        "ping"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new("ping", "Current bot status", CommandType::ChatInput)
+            .description_localizations(vec![("pl", "Aktualny stan bota")])
+            .validate()
+            .unwrap()
+            .build()
+    }
 
-pub async fn run(state: AppState, locale: Locale) -> Result<InteractionResponse, InteractionError> {
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(InteractionResponseData {
-            content: Some(locale.ping_last_restart(&state.start_time.timestamp())),
-            ..Default::default()
-        }),
-    })
+    async fn run(
+        &self,
+        state: AppState,
+        _interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                content: Some(locale.ping_last_restart(&state.start_time.timestamp())),
+                ..Default::default()
+            }),
+        })
+    }
 }
@@ -1,3 +1,4 @@
+use async_trait::async_trait;
 use tracing::debug;
 use twilight_model::{
     application::command::{Command, CommandType},
@@ -11,6 +12,7 @@ use twilight_util::builder::{
 use crate::{
     embeds::{Color, Extend, Project},
     interactions::{
+        commands::SlashCommand,
         context::{ApplicationCommandInteraction, GetOption},
         InteractionError,
     },
@@ -23,72 +25,82 @@ use crate::{
     state::AppState,
 };
 
-pub fn register() -> Command {
-    CommandBuilder::new(
-        "project",
-        "Get info about a Scratch project",
-        CommandType::ChatInput,
-    )
-    .description_localizations(vec![("pl", "Informacje o danym projekcie Scratch")])
-    .option(
-        StringBuilder::new("id", "Project URL or ID")
-            .required(true)
-            .description_localizations(vec![("pl", "Link lub ID projektu")]),
-    )
-    .validate()
-    .unwrap()
-    .build()
-}
+pub struct ProjectCommand;
 
-pub async fn run(
-    state: AppState,
-    interaction: ApplicationCommandInteraction,
-    locale: Locale,
-) -> Result<InteractionResponse, InteractionError> {
-    let id: &String = interaction.data().options.get_option("id")?;
+#[async_trait]
+impl SlashCommand for ProjectCommand {
+    fn name(&self) -> &'static str {
+        "project"
+    }
 
-    let Some(id) = extract_project_id(id) else {
-        return Ok(InteractionResponse {
-            kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(locale.invalid_project_id())
-                    .build(),
-            ),
-        });
-    };
+    fn register(&self) -> Command {
+        CommandBuilder::new(
+            "project",
+            "Get info about a Scratch project",
+            CommandType::ChatInput,
+        )
+        .description_localizations(vec![("pl", "Informacje o danym projekcie Scratch")])
+        .option(
+            StringBuilder::new("id", "Project URL or ID")
+                .required(true)
+                .description_localizations(vec![("pl", "Link lub ID projektu")]),
+        )
+        .validate()
+        .unwrap()
+        .build()
+    }
 
-    let (api, db) = tokio::join!(
-        state.reqwest_client.get_scratch_api_project(id),
-        state.reqwest_client.get_scratch_db_project(id),
-    );
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        let id: &String = interaction.data().options.get_option("id")?;
 
-    let response = match api? {
-        Some(api) => {
-            let mut project = Project::new();
-            debug!(?api);
-            project.extend(api);
+        let Some(id) = extract_project_id(id) else {
+            return Ok(InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .content(locale.invalid_project_id())
+                        .build(),
+                ),
+            });
+        };
 
-            if let Ok(Some(db)) = db {
-                debug!(?db);
-                project.extend(db);
-            }
-            debug!(?project);
+        let (api, db) = tokio::join!(
+            state.reqwest_client.get_scratch_api_project(id),
+            state.reqwest_client.get_scratch_db_project(id),
+        );
+
+        let response = match api? {
+            Some(api) => {
+                let mut project = Project::new();
+                debug!(?api);
+                project.extend(api);
 
-            let embed = project
-                .to_localized(locale)
-                .color(Color::Success.into())
-                .validate()?
-                .build();
+                if let Ok(Some(db)) = db {
+                    debug!(?db);
+                    project.extend(db);
+                }
+                debug!(?project);
 
-            InteractionResponseDataBuilder::new().embeds([embed])
-        }
-        None => InteractionResponseDataBuilder::new()
-            .content(locale.project_not_found(&project_link(id))),
-    };
+                let embed = project
+                    .to_localized(locale)
+                    .color(Color::Success.into())
+                    .validate()?
+                    .build();
 
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(response.build()),
-    })
+                InteractionResponseDataBuilder::new().embeds([embed])
+            }
+            None => InteractionResponseDataBuilder::new()
+                .content(locale.project_not_found(&project_link(id))),
+        };
+
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(response.build()),
+        })
+    }
 }
@@ -0,0 +1,236 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use twilight_mention::Mention;
+use twilight_model::{
+    application::command::{Command, CommandType},
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::RoleMarker, Id},
+};
+use twilight_util::builder::{
+    command::{CommandBuilder, IntegerBuilder, RoleBuilder, StringBuilder, SubCommandBuilder},
+    InteractionResponseDataBuilder,
+};
+
+use crate::{
+    database::{Database, RoleRule},
+    interactions::{
+        commands::SlashCommand,
+        context::{
+            command_options, ApplicationCommandInteraction, FromOptions, GetOption, GetSubcommand,
+        },
+        InteractionError,
+    },
+    locales::Locale,
+    roles::Condition,
+    state::AppState,
+};
+
+command_options! {
+    struct AddOptions {
+        role: Id<RoleMarker>,
+        condition: String,
+        value: Option<i64>,
+    }
+}
+
+pub struct RolesCommand;
+
+#[async_trait]
+impl SlashCommand for RolesCommand {
+    fn name(&self) -> &'static str {
+        "roles"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new(
+            "roles",
+            "Give roles automatically to linked members",
+            CommandType::ChatInput,
+        )
+        .description_localizations(vec![(
+            "pl",
+            "Automatycznie nadawaj role połączonym członkom",
+        )])
+        .default_member_permissions(Permissions::MANAGE_ROLES)
+        .dm_permission(false)
+        .option(
+            SubCommandBuilder::new("add", "Give a role to members matching a condition")
+                .description_localizations(vec![("pl", "Nadaj rolę członkom spełniającym warunek")])
+                .option(
+                    RoleBuilder::new("role", "Role to give")
+                        .required(true)
+                        .description_localizations(vec![("pl", "Rola do nadania")]),
+                )
+                .option(
+                    StringBuilder::new("condition", "Condition")
+                        .required(true)
+                        .description_localizations(vec![("pl", "Warunek")])
+                        .choices([
+                            ("Scratcher", "scratcher"),
+                            ("Followers", "followers"),
+                            ("Joined days ago", "joined"),
+                        ]),
+                )
+                .option(
+                    IntegerBuilder::new("value", "Followers or days, if needed")
+                        .min_value(0)
+                        .description_localizations(vec![(
+                            "pl",
+                            "Liczba śledzących lub dni, jeśli potrzebna",
+                        )]),
+                ),
+        )
+        .option(
+            SubCommandBuilder::new("remove", "Stop giving a role automatically")
+                .description_localizations(vec![("pl", "Przestań automatycznie nadawać rolę")])
+                .option(
+                    RoleBuilder::new("role", "Role")
+                        .required(true)
+                        .description_localizations(vec![("pl", "Rola")]),
+                ),
+        )
+        .option(
+            SubCommandBuilder::new("list", "List roles given automatically")
+                .description_localizations(vec![("pl", "Wyświetl automatycznie nadawane role")]),
+        )
+        .validate()
+        .unwrap()
+        .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        // Safe to unwrap because the command is disabled in DMs
+        let guild_id = interaction.guild_id.unwrap();
+
+        let (subcommand, options) = interaction.data().options.get_subcommand()?;
+
+        let content = match subcommand {
+            "add" => {
+                let AddOptions {
+                    role: role_id,
+                    condition,
+                    value,
+                } = AddOptions::from_options(options)?;
+
+                let condition = match (condition.as_str(), value) {
+                    ("scratcher", _) => Some(Condition::Scratcher),
+                    ("followers", Some(value)) => Some(Condition::Followers(value)),
+                    ("joined", Some(value)) => Some(Condition::JoinedDaysAgo(value)),
+                    _ => None,
+                };
+
+                if let Some(condition) = condition {
+                    if can_manage(&state, &interaction, role_id).await? {
+                        state
+                            .pool
+                            .write_role_rule(&RoleRule {
+                                guild_id,
+                                role_id,
+                                condition,
+                            })
+                            .await?;
+
+                        locale.role_rule_added(
+                            &condition.describe(locale),
+                            &role_id.mention().to_string(),
+                        )
+                    } else {
+                        locale.role_too_high(&role_id.mention().to_string())
+                    }
+                } else {
+                    locale.role_rule_value_required()
+                }
+            }
+            "remove" => {
+                let role_id: &Id<RoleMarker> = options.get_option("role")?;
+
+                match state.pool.delete_role_rule(guild_id, *role_id).await? {
+                    Some(_) => locale.role_rule_removed(&role_id.mention().to_string()),
+                    None => locale.role_rule_not_found(&role_id.mention().to_string()),
+                }
+            }
+            "list" => {
+                let rules = state.pool.get_guild_role_rules(guild_id).await?;
+
+                if rules.is_empty() {
+                    locale.no_role_rules()
+                } else {
+                    let mut content = locale.role_rules();
+
+                    for rule in rules {
+                        write!(
+                            content,
+                            "\n- {}: {}",
+                            rule.role_id.mention(),
+                            rule.condition.describe(locale)
+                        )
+                        .unwrap();
+                    }
+
+                    content
+                }
+            }
+            _ => panic!("unknown subcommand name"),
+        };
+
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(
+                InteractionResponseDataBuilder::new()
+                    .content(content)
+                    .allowed_mentions(Default::default())
+                    .flags(MessageFlags::EPHEMERAL)
+                    .build(),
+            ),
+        })
+    }
+}
+
+/// Members with Manage Roles can't give roles above their own, so they shouldn't be able to
+/// make the bot do it either.
+async fn can_manage(
+    state: &AppState,
+    interaction: &ApplicationCommandInteraction,
+    role_id: Id<RoleMarker>,
+) -> Result<bool, InteractionError> {
+    let member = interaction.member.as_ref().unwrap();
+
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(Permissions::ADMINISTRATOR))
+    {
+        return Ok(true);
+    }
+
+    let roles = state
+        .discord_client
+        .roles(interaction.guild_id.unwrap())
+        .await?
+        .models()
+        .await?;
+
+    let position = |id: Id<RoleMarker>| {
+        roles
+            .iter()
+            .find(|role| role.id == id)
+            .map(|role| role.position)
+            .unwrap_or(0)
+    };
+
+    let highest = member
+        .roles
+        .iter()
+        .map(|&id| position(id))
+        .max()
+        .unwrap_or(0);
+
+    Ok(position(role_id) < highest)
+}
@@ -0,0 +1,209 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use twilight_model::{
+    application::command::{Command, CommandType},
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::{
+    command::{CommandBuilder, StringBuilder, SubCommandBuilder},
+    InteractionResponseDataBuilder,
+};
+
+use crate::{
+    database::{Database, StatsSnapshot},
+    embeds::timestamp,
+    interactions::{
+        commands::SlashCommand,
+        context::{ApplicationCommandInteraction, GetOption, GetSubcommand},
+        InteractionError,
+    },
+    locales::{Locale, ToLocalized},
+    scratch::site::{extract_username, user_link},
+    state::AppState,
+};
+
+/// Snapshots shown at once, a month of daily updates.
+const HISTORY_LENGTH: i64 = 30;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub struct StatsCommand;
+
+#[async_trait]
+impl SlashCommand for StatsCommand {
+    fn name(&self) -> &'static str {
+        "stats"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new(
+            "stats",
+            "Statistics of Scratch accounts",
+            CommandType::ChatInput,
+        )
+        .description_localizations(vec![("pl", "Statystyki kont Scratch")])
+        .option(
+            SubCommandBuilder::new("history", "Show how the stats of a linked account changed")
+                .description_localizations(vec![(
+                    "pl",
+                    "Pokaż, jak zmieniały się statystyki połączonego konta",
+                )])
+                .option(
+                    StringBuilder::new("username", "Account URL or username")
+                        .required(true)
+                        .description_localizations(vec![(
+                            "pl",
+                            "Link do konta lub nazwa użytkownika",
+                        )]),
+                ),
+        )
+        .validate()
+        .unwrap()
+        .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        let (subcommand, options) = interaction.data().options.get_subcommand()?;
+
+        let content = match subcommand {
+            "history" => {
+                let username: &String = options.get_option("username")?;
+
+                match extract_username(username) {
+                    Some(username) => {
+                        let history = state
+                            .pool
+                            .get_stats_history(username.to_string(), HISTORY_LENGTH)
+                            .await?;
+
+                        describe_history(&username, &history, locale)
+                    }
+                    None => locale.invalid_username(),
+                }
+            }
+            _ => panic!("unknown subcommand name"),
+        };
+
+        Ok(InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(
+                InteractionResponseDataBuilder::new()
+                    .content(content)
+                    .allowed_mentions(Default::default())
+                    .build(),
+            ),
+        })
+    }
+}
+
+fn describe_history(username: &str, history: &[StatsSnapshot], locale: Locale) -> String {
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return locale.stats_history_empty(&user_link(username));
+    };
+
+    let mut content =
+        locale.stats_history_title(&timestamp(first.recorded_at), &user_link(&last.username));
+
+    let followers: Vec<_> = history.iter().map(|snapshot| snapshot.followers).collect();
+    write!(
+        content,
+        "\n- {}",
+        locale.stats_history_followers(
+            &first.followers.to_string(),
+            &last.followers.to_string(),
+            &sparkline(&followers),
+        )
+    )
+    .unwrap();
+
+    let describe_status = |snapshot: &StatsSnapshot| match &snapshot.status {
+        Some(status) => status.to_localized(locale),
+        None => locale.stats_history_unknown(),
+    };
+
+    // Only the changes, since the status rarely changes more than once
+    let mut statuses = describe_status(first);
+    for (previous, snapshot) in history.iter().zip(&history[1..]) {
+        if snapshot.status != previous.status {
+            write!(
+                statuses,
+                " → {} ({})",
+                describe_status(snapshot),
+                timestamp(snapshot.recorded_at)
+            )
+            .unwrap();
+        }
+    }
+    write!(content, "\n- {}", locale.stats_history_status(&statuses)).unwrap();
+
+    content
+}
+
+/// Scales the values between the lowest and the highest one.
+fn sparkline(values: &[i64]) -> String {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+
+    let steps = SPARKS.len() as i64 - 1;
+    let range = (max - min).max(1);
+
+    values
+        .iter()
+        .map(|value| SPARKS[((value - min) * steps / range) as usize])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::scratch::db::user::Status;
+
+    use super::*;
+
+    #[test]
+    fn sparkline_scaled() {
+        assert_eq!(sparkline(&[0, 7, 14]), "▁▄█");
+        assert_eq!(sparkline(&[100, 101, 107]), "▁▂█");
+    }
+
+    #[test]
+    fn sparkline_flat() {
+        assert_eq!(sparkline(&[5, 5, 5]), "▁▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn status_changes() {
+        let snapshot = |day, followers, status| StatsSnapshot {
+            username: "PMJ_Studio".into(),
+            recorded_at: datetime!(2023-08-01 12:00:00 UTC) + time::Duration::days(day),
+            followers,
+            status: Some(status),
+        };
+
+        let content = describe_history(
+            "pmj_studio",
+            &[
+                snapshot(0, 10, Status::NewScratcher),
+                snapshot(1, 12, Status::NewScratcher),
+                snapshot(2, 20, Status::Scratcher),
+            ],
+            Locale::En,
+        );
+
+        assert!(content.contains("10 → 20"), "{content}");
+        assert!(content.contains("▁▂█"), "{content}");
+        assert!(
+            content.contains("New Scratcher → Scratcher (<t:1691064000:R>)"),
+            "{content}"
+        );
+    }
+}
@@ -1,16 +1,23 @@
-use tracing::debug;
+use async_trait::async_trait;
+use tracing::{debug, error, warn};
 use twilight_model::{
     application::command::{Command, CommandType},
-    http::interaction::{InteractionResponse, InteractionResponseType},
+    http::{
+        attachment::Attachment,
+        interaction::{InteractionResponse, InteractionResponseType},
+    },
 };
 use twilight_util::builder::{
-    command::{CommandBuilder, StringBuilder},
+    command::{BooleanBuilder, CommandBuilder, StringBuilder},
+    embed::{EmbedBuilder, ImageSource},
     InteractionResponseDataBuilder,
 };
 
 use crate::{
+    card::decode_avatar,
     embeds::{Color, Extend, User},
     interactions::{
+        commands::SlashCommand,
         context::{ApplicationCommandInteraction, GetOption},
         InteractionError,
     },
@@ -19,76 +26,197 @@ use crate::{
         api::ScratchAPIClient,
         db::ScratchDBClient,
         site::{extract_username, user_link},
+        ScratchAPIError,
     },
     state::AppState,
 };
 
-pub fn register() -> Command {
-    CommandBuilder::new(
-        "user",
-        "Get info about a Scratch user",
-        CommandType::ChatInput,
-    )
-    .description_localizations(vec![("pl", "Informacje o danym koncie Scratch")])
-    .option(
-        StringBuilder::new("username", "Account URL or username")
-            .required(true)
-            .description_localizations(vec![("pl", "Link do konta lub nazwa użytkownika")]),
-    )
-    .validate()
-    .unwrap()
-    .build()
-}
+const CARD_FILENAME: &str = "card.png";
+
+pub struct UserCommand;
+
+#[async_trait]
+impl SlashCommand for UserCommand {
+    fn name(&self) -> &'static str {
+        "user"
+    }
+
+    fn register(&self) -> Command {
+        CommandBuilder::new(
+            "user",
+            "Get info about a Scratch user",
+            CommandType::ChatInput,
+        )
+        .description_localizations(vec![("pl", "Informacje o danym koncie Scratch")])
+        .option(
+            StringBuilder::new("username", "Account URL or username")
+                .required(true)
+                .description_localizations(vec![("pl", "Link do konta lub nazwa użytkownika")]),
+        )
+        .option(
+            BooleanBuilder::new("card", "Attach a profile card image")
+                .description_localizations(vec![("pl", "Załącz obrazek z kartą profilu")]),
+        )
+        .validate()
+        .unwrap()
+        .build()
+    }
+
+    async fn run(
+        &self,
+        state: AppState,
+        interaction: ApplicationCommandInteraction,
+        locale: Locale,
+    ) -> Result<InteractionResponse, InteractionError> {
+        let username: &String = interaction.data().options.get_option("username")?;
+        let card: Option<&bool> = interaction.data().options.get_optional("card")?;
+        let card = card.copied().unwrap_or(false);
+
+        let Some(username) = extract_username(username) else {
+            return Ok(InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .content(locale.invalid_username())
+                        .build(),
+                ),
+            });
+        };
+
+        if card {
+            // Downloading the avatar and drawing the card can take longer than Discord waits
+            let token = interaction.token.to_owned();
+            tokio::spawn(async move {
+                respond_with_card(&state, &token, &username, locale).await;
+            });
+
+            return Ok(InteractionResponse {
+                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                data: None,
+            });
+        }
+
+        let response = match get_user(&state, &username).await? {
+            Some(user) => {
+                let embed = embed(&user, locale).validate()?.build();
 
-pub async fn run(
-    state: AppState,
-    interaction: ApplicationCommandInteraction,
-    locale: Locale,
-) -> Result<InteractionResponse, InteractionError> {
-    let username: &String = interaction.data().options.get_option("username")?;
+                InteractionResponseDataBuilder::new().embeds([embed])
+            }
+            None => InteractionResponseDataBuilder::new()
+                .content(locale.user_not_found(&user_link(&username))),
+        };
 
-    let Some(username) = extract_username(username) else {
-        return Ok(InteractionResponse {
+        Ok(InteractionResponse {
             kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(locale.invalid_username())
-                    .build(),
-            ),
-        });
-    };
+            data: Some(response.build()),
+        })
+    }
+}
 
+async fn get_user(state: &AppState, username: &str) -> Result<Option<User>, ScratchAPIError> {
     let (api, db) = tokio::join!(
-        state.reqwest_client.get_scratch_api_user(&username),
-        state.reqwest_client.get_scratch_db_user(&username),
+        state.reqwest_client.get_scratch_api_user(username),
+        state.reqwest_client.get_scratch_db_user(username),
     );
 
-    let response = match api? {
-        Some(api) => {
-            let mut user = User::new();
-            debug!(?api);
-            user.extend(api);
+    let Some(api) = api? else {
+        return Ok(None);
+    };
+
+    let mut user = User::new();
+    debug!(?api);
+    user.extend(api);
+
+    if let Ok(Some(db)) = db {
+        debug!(?db);
+        user.extend(db);
+    }
+    debug!(?user);
 
-            if let Ok(Some(db)) = db {
-                debug!(?db);
-                user.extend(db);
+    Ok(Some(user))
+}
+
+fn embed(user: &User, locale: Locale) -> EmbedBuilder {
+    user.to_localized(locale).color(Color::Success.into())
+}
+
+/// Edits the deferred response with the embed and the card, or only the embed if drawing fails.
+async fn respond_with_card(state: &AppState, token: &str, username: &str, locale: Locale) {
+    let result = async {
+        let client = state
+            .discord_client
+            .interaction(state.config.client_id.parse()?);
+
+        let Some(user) = get_user(state, username).await? else {
+            client
+                .update_response(token)
+                .content(Some(&locale.user_not_found(&user_link(username))))?
+                .await?;
+
+            return anyhow::Ok(());
+        };
+
+        let (embed, attachments, content) = match draw_card(state, &user, locale).await {
+            Ok(png) => (
+                embed(&user, locale).image(ImageSource::attachment(CARD_FILENAME)?),
+                vec![Attachment::from_bytes(CARD_FILENAME.into(), png, 0)],
+                None,
+            ),
+            Err(err) => {
+                error!("failed to draw the card of {username}: {err}");
+                (embed(&user, locale), Vec::new(), Some(locale.card_failed()))
             }
-            debug!(?user);
+        };
+
+        client
+            .update_response(token)
+            .content(content.as_deref())?
+            .embeds(Some(&[embed.validate()?.build()]))?
+            .attachments(&attachments)?
+            .await?;
 
-            let embed = user
-                .to_localized(locale)
-                .color(Color::Success.into())
-                .validate()?
-                .build();
+        anyhow::Ok(())
+    }
+    .await;
 
-            InteractionResponseDataBuilder::new().embeds([embed])
+    if let Err(err) = result {
+        error!("/user {username} failed: {err}");
+
+        let result = async {
+            state
+                .discord_client
+                .interaction(state.config.client_id.parse()?)
+                .update_response(token)
+                .content(Some(&locale.user_failed(&user_link(username))))?
+                .await?;
+
+            anyhow::Ok(())
         }
-        None => InteractionResponseDataBuilder::new()
-            .content(locale.user_not_found(&user_link(&username))),
+        .await;
+
+        if let Err(err) = result {
+            error!("{}", err);
+        }
+    }
+}
+
+async fn draw_card(state: &AppState, user: &User, locale: Locale) -> anyhow::Result<Vec<u8>> {
+    // A missing avatar is drawn as a placeholder, it's not worth failing the whole card
+    let avatar = match user.avatar() {
+        Some(url) => match download(state, url).await {
+            Ok(bytes) => decode_avatar(&bytes),
+            Err(err) => {
+                warn!("failed to download avatar {url}: {err}");
+                None
+            }
+        },
+        None => None,
     };
 
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(response.build()),
-    })
+    user.card(locale, avatar).to_png()
+}
+
+async fn download(state: &AppState, url: &str) -> reqwest::Result<Vec<u8>> {
+    let response = state.reqwest_client.get(url).send().await?;
+    Ok(response.error_for_status()?.bytes().await?.to_vec())
 }
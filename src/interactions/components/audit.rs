@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use twilight_model::{
+    channel::message::{
+        component::{Button, ButtonStyle},
+        Component,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+
+use crate::{
+    interactions::{
+        commands::audit::respond, context::MessageComponentInteraction, InteractionError,
+    },
+    locales::Locale,
+    state::AppState,
+};
+
+use super::ComponentCustomId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomId {
+    pub page: usize,
+}
+
+pub fn build(custom_id: CustomId, label: String, disabled: bool) -> Component {
+    Component::Button(Button {
+        custom_id: ComponentCustomId::Audit(custom_id).into(),
+        disabled,
+        emoji: None,
+        label: Some(label),
+        style: ButtonStyle::Secondary,
+        url: None,
+    })
+}
+
+pub async fn run(
+    state: AppState,
+    interaction: MessageComponentInteraction,
+    custom_id: CustomId,
+    locale: Locale,
+) -> Result<InteractionResponse, InteractionError> {
+    // Safe to unwrap because `/audit` is disabled in DMs
+    let guild_id = interaction.guild_id.unwrap();
+
+    // Members could have joined or linked their accounts since the last page
+    let token = interaction.token.to_owned();
+    tokio::spawn(async move {
+        respond(&state, &token, guild_id, custom_id.page, false, locale).await;
+    });
+
+    Ok(InteractionResponse {
+        kind: InteractionResponseType::DeferredUpdateMessage,
+        data: None,
+    })
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use twilight_model::{
+    channel::message::{
+        component::{Button, ButtonStyle},
+        Component,
+    },
+    http::interaction::InteractionResponse,
+};
+
+use crate::{
+    database::Database,
+    interactions::{
+        components::flow::FlowMessage, context::MessageComponentInteraction, InteractionError,
+    },
+    locales::Locale,
+    scratch::site::user_link,
+    state::AppState,
+};
+
+use super::ComponentCustomId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomId {
+    pub username: String,
+}
+
+pub fn build(custom_id: CustomId, locale: Locale) -> Component {
+    Component::Button(Button {
+        custom_id: ComponentCustomId::Cancel(custom_id).into(),
+        disabled: false,
+        emoji: None,
+        label: Some(locale.cancel()),
+        style: ButtonStyle::Secondary,
+        url: None,
+    })
+}
+
+pub async fn run(
+    state: AppState,
+    interaction: MessageComponentInteraction,
+    custom_id: CustomId,
+    locale: Locale,
+) -> Result<InteractionResponse, InteractionError> {
+    let author_id = interaction.author_id().unwrap();
+
+    let message = match state
+        .pool
+        .delete_pending_verification(author_id, custom_id.username.to_owned())
+        .await?
+    {
+        Some(verification) => FlowMessage::finished(
+            &state,
+            &verification,
+            locale,
+            locale.verification_cancelled(&user_link(&custom_id.username)),
+        ),
+        None => FlowMessage::text(locale.code_expired()),
+    };
+
+    Ok(message.into_response())
+}
@@ -1,23 +1,23 @@
-use rand::distributions::{Alphanumeric, DistString};
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
-use twilight_mention::Mention;
 use twilight_model::{
     channel::message::{
-        component::{ActionRow, Button, ButtonStyle},
-        Component, MessageFlags,
+        component::{Button, ButtonStyle},
+        Component,
     },
     http::interaction::{InteractionResponse, InteractionResponseType},
     id::{marker::UserMarker, Id},
 };
-use twilight_util::builder::InteractionResponseDataBuilder;
 
 use crate::{
-    database::Database,
-    interactions::{components::done, context::MessageComponentInteraction, InteractionError},
+    database::{Database, PendingVerification},
+    interactions::{
+        components::flow::FlowMessage, context::MessageComponentInteraction, InteractionError,
+    },
     locales::Locale,
     scratch::site::user_link,
     state::AppState,
+    verification::Method,
 };
 
 use super::ComponentCustomId;
@@ -26,14 +26,25 @@ use super::ComponentCustomId;
 pub struct CustomId {
     pub username: String,
     pub id: Id<UserMarker>,
+    /// Missing in buttons created before cloud variables, which were all comments
+    #[serde(default)]
+    pub method: Method,
+    /// Missing in buttons created before it was added
+    #[serde(default)]
+    pub scratch_id: Option<i64>,
 }
 
 pub fn build(custom_id: CustomId, locale: Locale) -> Component {
+    let label = match custom_id.method {
+        Method::Comment => locale.generate_code(),
+        Method::Cloud => locale.generate_cloud_code(),
+    };
+
     Component::Button(Button {
         custom_id: ComponentCustomId::Code(custom_id).into(),
         disabled: false,
         emoji: None,
-        label: Some(locale.generate_code()),
+        label: Some(label),
         style: ButtonStyle::Primary,
         url: None,
     })
@@ -47,59 +58,49 @@ pub async fn run(
 ) -> Result<InteractionResponse, InteractionError> {
     let author_id = interaction.author_id().unwrap();
 
+    // The message is ephemeral, so this only happens with forged custom IDs
     if author_id != custom_id.id {
-        // TODO: respond with "not for you"
-        todo!();
+        return Ok(InteractionResponse {
+            kind: InteractionResponseType::DeferredUpdateMessage,
+            data: None,
+        });
     }
 
     let already_linked = state
         .pool
-        .get_scratch_account(custom_id.username.to_string())
+        .get_scratch_account(custom_id.username.to_string(), custom_id.scratch_id)
         .await?;
 
-    if let Some(account) = already_linked {
-        let content = if account.id == author_id {
-            locale.already_linked_to_you(&user_link(&custom_id.username))
-        } else {
-            locale.already_linked_to_other(
-                &author_id.mention().to_string(),
-                &user_link(&custom_id.username),
-            )
-        };
+    // Accounts linked to someone else are transferred once verified
+    if already_linked.is_some_and(|account| account.id == author_id) {
+        let content = locale.already_linked_to_you(&user_link(&custom_id.username));
 
-        return Ok(InteractionResponse {
-            kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(content)
-                    .allowed_mentions(Default::default())
-                    .build(),
-            ),
-        });
+        return Ok(FlowMessage::text(content).into_response());
     }
 
-    let code = Alphanumeric.sample_string(&mut rand::thread_rng(), 20);
-    let generated = OffsetDateTime::now_utc();
+    let generated_at = OffsetDateTime::now_utc();
 
-    let done_button = done::build(
-        done::CustomId {
-            username: custom_id.username,
-            code: code.to_owned(),
-            generated,
-        },
-        locale,
-    );
+    let studio_id = match custom_id.method {
+        Method::Comment => Some(state.studios.pick()),
+        Method::Cloud => None,
+    };
 
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(
-            InteractionResponseDataBuilder::new()
-                .content(code)
-                .components([Component::ActionRow(ActionRow {
-                    components: vec![done_button],
-                })])
-                .flags(MessageFlags::EPHEMERAL)
-                .build(),
-        ),
-    })
+    // Replaces the previous code of the user, also one for another account
+    let verification = state
+        .pool
+        .write_pending_verification(&PendingVerification {
+            id: author_id,
+            username: custom_id.username.to_owned(),
+            code: custom_id.method.generate_code(),
+            method: custom_id.method,
+            generated_at,
+            expires_at: generated_at + state.config.verification_expiry,
+            interaction_token: Some(interaction.token.to_owned()),
+            locale: interaction.locale.to_owned(),
+            studio_id,
+            guild_id: interaction.guild_id,
+        })
+        .await?;
+
+    Ok(FlowMessage::code(&state, &verification, locale, None, false).into_response())
 }
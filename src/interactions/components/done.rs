@@ -1,26 +1,31 @@
 use serde::{Deserialize, Serialize};
-use time::{Duration, OffsetDateTime};
+use time::OffsetDateTime;
+use tracing::error;
 use twilight_mention::Mention;
 use twilight_model::{
     channel::message::{
         component::{Button, ButtonStyle},
         Component,
     },
-    http::interaction::{InteractionResponse, InteractionResponseType},
+    http::interaction::InteractionResponse,
 };
-use twilight_util::builder::InteractionResponseDataBuilder;
 
 use crate::{
-    database::{link_account, Database, LinkError},
-    interactions::{context::MessageComponentInteraction, InteractionError},
-    linked_roles::RoleConnectionUpdater,
+    database::{Database, LinkError, PendingVerification},
+    event_log::{self, Event},
+    interactions::{
+        components::flow::FlowMessage, context::MessageComponentInteraction, InteractionError,
+    },
     locales::Locale,
     scratch::{
-        api::{studio::Comment, ScratchAPIClient},
-        site::user_link,
-        STUDIO_ID,
+        api::ScratchAPIClient, cloud::ScratchCloudClient, site::user_link, DEFAULT_STUDIO_ID,
     },
     state::AppState,
+    verification::{
+        complete, success_message,
+        validate::{validate_cloud, validate_comment, ValidationError},
+        Method,
+    },
 };
 
 use super::ComponentCustomId;
@@ -28,17 +33,19 @@ use super::ComponentCustomId;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CustomId {
     pub username: String,
-    pub code: String,
-    #[serde(with = "time::serde::iso8601")]
-    pub generated: OffsetDateTime,
 }
 
-pub fn build(custom_id: CustomId, locale: Locale) -> Component {
+pub fn build(custom_id: CustomId, method: Method, locale: Locale) -> Component {
+    let label = match method {
+        Method::Comment => locale.verify_comment(),
+        Method::Cloud => locale.verify_cloud(),
+    };
+
     Component::Button(Button {
         custom_id: ComponentCustomId::Done(custom_id).into(),
         disabled: false,
         emoji: None,
-        label: Some(locale.verify_comment()),
+        label: Some(label),
         style: ButtonStyle::Primary,
         url: None,
     })
@@ -51,255 +58,143 @@ pub async fn run(
     locale: Locale,
 ) -> Result<InteractionResponse, InteractionError> {
     let author_id = interaction.author_id().unwrap();
-    let expires = custom_id.generated.saturating_add(Duration::minutes(5));
 
-    if OffsetDateTime::now_utc() > expires {
-        return Ok(InteractionResponse {
-            kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(locale.code_expired())
-                    .build(),
-            ),
-        });
-    }
-
-    let comments = state
-        .reqwest_client
-        .get_scratch_api_studio_comments(STUDIO_ID)
-        .await?
-        // Assume the studio hasn't been deleted
-        .unwrap();
-
-    if let Err(err) = validate_comment(comments, custom_id.to_owned()) {
-        let message = match err {
-            ValidateCommentError::CommentNotFound => locale.comment_not_found(),
-            ValidateCommentError::InvalidAccount(actual) => {
-                locale.wrong_account(&user_link(&actual), &user_link(&custom_id.username))
-            }
-            ValidateCommentError::InvalidCode(_) => locale.invalid_code(),
-        };
+    let verification = state
+        .pool
+        .get_pending_verification(author_id, custom_id.username)
+        .await?;
 
-        return Ok(InteractionResponse {
-            kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(message)
-                    .build(),
-            ),
-        });
+    // Expired verifications might not have been cleaned up yet
+    let Some(verification) =
+        verification.filter(|verification| OffsetDateTime::now_utc() <= verification.expires_at)
+    else {
+        return Ok(FlowMessage::text(locale.code_expired()).into_response());
     };
 
-    if let Err(err) = link_account(&state.pool, custom_id.username.to_owned(), author_id).await? {
-        let message = match err {
-            LinkError::AlreadyLinkedToYou => {
-                locale.already_linked_to_you(&user_link(&custom_id.username))
+    let verifying = FlowMessage::code(
+        &state,
+        &verification,
+        locale,
+        Some(locale.verifying()),
+        true,
+    );
+
+    // Checking the comments can take longer than Discord waits for a response,
+    // so the result replaces the "Verifying…" message once it's ready
+    let token = interaction.token.to_owned();
+    tokio::spawn(async move {
+        let message = match verify(&state, &verification, locale).await {
+            Ok(message) => message,
+            Err(err) => {
+                error!("{}", err);
+                // Let the user try again
+                FlowMessage::code(&state, &verification, locale, None, false)
             }
-            LinkError::AlreadyLinkedToOther(id) => locale.already_linked_to_other(
-                &id.mention().to_string(),
-                &user_link(&custom_id.username),
-            ),
         };
 
-        return Ok(InteractionResponse {
-            kind: InteractionResponseType::ChannelMessageWithSource,
-            data: Some(
-                InteractionResponseDataBuilder::new()
-                    .content(message)
-                    .allowed_mentions(Default::default())
-                    .build(),
-            ),
-        });
-    }
-
-    if state.pool.get_token(author_id).await?.is_some() {
-        state.update_role_connection(author_id).await.unwrap(); // TODO: don't
-    }
-
-    let message = format!(
-        "{}\n\n{}",
-        locale.successfully_linked(
-            &author_id.mention().to_string(),
-            &user_link(&custom_id.username),
-        ),
-        locale.linked_roles_message(),
-    );
+        if let Err(err) = message.edit(&state, &token).await {
+            error!("{}", err);
+        }
+    });
 
-    Ok(InteractionResponse {
-        kind: InteractionResponseType::ChannelMessageWithSource,
-        data: Some(
-            InteractionResponseDataBuilder::new()
-                .content(message)
-                .allowed_mentions(Default::default())
-                .build(),
-        ),
-    })
+    Ok(verifying.into_response())
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum ValidateCommentError {
-    InvalidAccount(String),
-    InvalidCode(String),
-    CommentNotFound,
-}
+async fn verify(
+    state: &AppState,
+    verification: &PendingVerification,
+    locale: Locale,
+) -> Result<FlowMessage, InteractionError> {
+    let result = match verification.method {
+        Method::Comment => {
+            let studio_id = verification.studio_id.unwrap_or(DEFAULT_STUDIO_ID);
+
+            let comments = state
+                .reqwest_client
+                .get_scratch_api_studio_comments(studio_id)
+                .await?
+                // The studio could have been deleted in the meantime
+                .unwrap_or_default();
+
+            validate_comment(comments, verification)
+        }
+        Method::Cloud => {
+            // Safe to unwrap because the cloud button is only shown when the project is configured
+            let project_id = state.config.cloud_project_id.unwrap();
 
-fn validate_comment(
-    comments: Vec<Comment>,
-    custom_id: CustomId,
-) -> Result<(), ValidateCommentError> {
-    let comments: Vec<_> = comments
-        .into_iter()
-        .filter(|comment| comment.datetime_created > custom_id.generated)
-        .collect();
+            let logs = state
+                .reqwest_client
+                .get_scratch_cloud_logs(project_id)
+                .await?;
 
-    let valid_code = |comment: &&Comment| comment.content.trim() == custom_id.code;
-    let valid_username = |comment: &&Comment| {
-        comment.author.username.to_lowercase() == custom_id.username.to_lowercase()
+            validate_cloud(logs, verification)
+        }
     };
 
-    if let Some(_) = comments.iter().filter(valid_code).find(valid_username) {
-        Ok(())
-    } else {
-        Err(if let Some(comment) = comments.iter().find(valid_code) {
-            ValidateCommentError::InvalidAccount(comment.author.username.to_string())
-        } else if let Some(comment) = comments.iter().find(valid_username) {
-            ValidateCommentError::InvalidCode(comment.content.to_string())
-        } else {
-            ValidateCommentError::CommentNotFound
-        })
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use time::macros::datetime;
-
-    use crate::scratch::api::studio::Author;
+    if let Err(err) = result {
+        let event = match &err {
+            ValidationError::InvalidAccount(actual) => Some(Event::WrongAccount {
+                id: verification.id,
+                expected: verification.username.to_owned(),
+                actual: actual.to_owned(),
+            }),
+            ValidationError::InvalidCode(_) => Some(Event::WrongCode {
+                id: verification.id,
+                username: verification.username.to_owned(),
+            }),
+            ValidationError::NotFound => None,
+        };
 
-    use super::*;
+        if let Some(event) = event {
+            event_log::log(state, verification.guild_id, event);
+        }
 
-    fn comment(code: &str, username: &str) -> Comment {
-        Comment {
-            id: 225945888,
-            parent_id: None,
-            commentee_id: None,
-            content: code.into(),
-            datetime_created: datetime!(2023-06-08 16:01:00.000 UTC),
-            datetime_modified: datetime!(2023-06-08 16:01:00.000 UTC),
-            visibility: "visible".into(),
-            author: Author {
-                id: 106748322,
-                username: username.into(),
-                scratchteam: false,
-                image: "https://cdn2.scratch.mit.edu/get_image/user/106748322_60x60.png".into(),
+        let status = match err {
+            ValidationError::NotFound => match verification.method {
+                Method::Comment => locale.comment_not_found(),
+                Method::Cloud => locale.cloud_variable_not_found(),
             },
-            reply_count: 0,
-        }
-    }
+            ValidationError::InvalidAccount(actual) => {
+                locale.wrong_account(&user_link(&actual), &user_link(&verification.username))
+            }
+            ValidationError::InvalidCode(_) => locale.invalid_code(),
+        };
 
-    fn comments(data: &[(&str, &str)]) -> Vec<Comment> {
-        data.into_iter()
-            .map(|(code, username)| comment(code, username))
-            .collect()
-    }
+        // The code is still valid, so the buttons stay enabled
+        return Ok(FlowMessage::code(
+            state,
+            verification,
+            locale,
+            Some(status),
+            false,
+        ));
+    };
 
-    fn custom_id() -> CustomId {
-        CustomId {
-            code: "code1".into(),
-            generated: datetime!(2023-06-08 16:00:00.000 UTC),
-            username: "username1".into(),
+    let content = match complete(state, verification).await? {
+        Some(Ok(())) => success_message(locale, verification.id, &verification.username),
+        Some(Err(LinkError::AlreadyLinkedToYou)) => {
+            locale.already_linked_to_you(&user_link(&verification.username))
         }
-    }
-
-    #[test]
-    fn ok() {
-        let comments = comments(&[
-            ("code1", "username1"),
-            ("code2", "username1"),
-            ("code1", "username2"),
-            ("code2", "username2"),
-        ]);
-
-        let result = validate_comment(comments, custom_id());
-
-        assert_eq!(result, Ok(()));
-    }
-
-    #[test]
-    fn comment_not_found() {
-        let comments = comments(&[("code2", "username2"), ("code3", "username3")]);
-
-        let result = validate_comment(comments, custom_id());
-
-        assert_eq!(result, Err(ValidateCommentError::CommentNotFound));
-    }
-
-    #[test]
-    fn invalid_code() {
-        let comments = comments(&[
-            ("code2", "username1"),
-            ("code2", "username2"),
-            ("code3", "username3"),
-        ]);
-
-        let result = validate_comment(comments, custom_id());
-
-        assert_eq!(
-            result,
-            Err(ValidateCommentError::InvalidCode("code2".into()))
-        );
-    }
-
-    #[test]
-    fn invalid_account() {
-        let comments = comments(&[
-            ("code1", "username2"),
-            ("code2", "username2"),
-            ("code3", "username3"),
-        ]);
-
-        let result = validate_comment(comments, custom_id());
-
-        assert_eq!(
-            result,
-            Err(ValidateCommentError::InvalidAccount("username2".into()))
-        );
-    }
-
-    #[test]
-    fn invalid_both() {
-        let comments = comments(&[
-            ("code1", "username2"),
-            ("code2", "username1"),
-            ("code2", "username2"),
-            ("code3", "username3"),
-        ]);
-
-        let result = validate_comment(comments, custom_id());
-
-        assert_eq!(
-            result,
-            Err(ValidateCommentError::InvalidAccount("username2".into()))
-        );
-    }
-
-    #[test]
-    fn too_early() {
-        let comments = comments(&[
-            ("code1", "username1"),
-            ("code2", "username1"),
-            ("code1", "username2"),
-            ("code2", "username2"),
-        ]);
-
-        let custom_id = CustomId {
-            code: "code1".into(),
-            generated: datetime!(2023-06-08 17:00:00.000 UTC),
-            username: "username1".into(),
-        };
-
-        let result = validate_comment(comments, custom_id);
+        Some(Err(LinkError::AlreadyLinkedToOther(id))) => locale.already_linked_to_other(
+            &id.mention().to_string(),
+            &user_link(&verification.username),
+        ),
+        // Completed by the poller in the meantime, which could have edited the message before
+        // "Verifying…" replaced it, so the result is shown again
+        None => {
+            let linked = state
+                .pool
+                .get_scratch_account(verification.username.to_owned(), None)
+                .await?;
+
+            match linked {
+                Some(account) if account.id == verification.id => {
+                    success_message(locale, verification.id, &verification.username)
+                }
+                _ => return Ok(FlowMessage::text(locale.code_expired())),
+            }
+        }
+    };
 
-        assert_eq!(result, Err(ValidateCommentError::CommentNotFound));
-    }
+    Ok(FlowMessage::finished(state, verification, locale, content))
 }
@@ -0,0 +1,169 @@
+use twilight_mention::Mention;
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        AllowedMentions, Component,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    database::PendingVerification,
+    embeds::timestamp,
+    locales::Locale,
+    scratch::{
+        site::{project_url, studio_comments_url, user_link},
+        DEFAULT_STUDIO_ID,
+    },
+    state::AppState,
+    verification::Method,
+};
+
+use super::{cancel, done};
+
+/// The `/link` message, which is updated in place at every step of the flow.
+#[derive(Debug, Clone)]
+pub struct FlowMessage {
+    content: String,
+    components: Vec<Component>,
+}
+
+impl FlowMessage {
+    /// Just the content, without any buttons.
+    pub fn text(content: String) -> Self {
+        Self {
+            content,
+            components: Vec::new(),
+        }
+    }
+
+    /// The code with instructions and buttons, optionally with the result of the last step.
+    pub fn code(
+        state: &AppState,
+        verification: &PendingVerification,
+        locale: Locale,
+        status: Option<String>,
+        disabled: bool,
+    ) -> Self {
+        let mention = verification.id.mention().to_string();
+        let user = user_link(&verification.username);
+
+        let instructions = match verification.method {
+            Method::Comment => locale.link_your_account(&mention, &user),
+            Method::Cloud => locale.set_cloud_variable(&mention, &user),
+        };
+
+        let mut content = format!(
+            "{}\n`{}`\n{}",
+            instructions,
+            verification.code,
+            locale.code_expires(&timestamp(verification.expires_at)),
+        );
+
+        if let Some(status) = status {
+            content.push_str("\n\n");
+            content.push_str(&status);
+        }
+
+        Self {
+            content,
+            components: buttons(state, verification, locale, disabled),
+        }
+    }
+
+    /// Replaces the code with the final result and disables the buttons.
+    pub fn finished(
+        state: &AppState,
+        verification: &PendingVerification,
+        locale: Locale,
+        content: String,
+    ) -> Self {
+        Self {
+            content,
+            components: buttons(state, verification, locale, true),
+        }
+    }
+
+    pub fn into_response(self) -> InteractionResponse {
+        InteractionResponse {
+            kind: InteractionResponseType::UpdateMessage,
+            data: Some(
+                InteractionResponseDataBuilder::new()
+                    .content(self.content)
+                    .components(self.components)
+                    .allowed_mentions(Default::default())
+                    .build(),
+            ),
+        }
+    }
+
+    /// Edits the message through the token of an interaction which responded with it.
+    pub async fn edit(&self, state: &AppState, token: &str) -> anyhow::Result<()> {
+        state
+            .discord_client
+            .interaction(state.config.client_id.parse()?)
+            .update_response(token)
+            .content(Some(&self.content))?
+            .components(Some(&self.components))?
+            .allowed_mentions(Some(&AllowedMentions::default()))
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn buttons(
+    state: &AppState,
+    verification: &PendingVerification,
+    locale: Locale,
+    disabled: bool,
+) -> Vec<Component> {
+    let (label, url) = match verification.method {
+        Method::Comment => (
+            locale.go_to_studio(),
+            // Verifications from before the studio pool don't have one
+            Some(studio_comments_url(
+                verification.studio_id.unwrap_or(DEFAULT_STUDIO_ID),
+            )),
+        ),
+        Method::Cloud => (
+            locale.go_to_project(),
+            state.config.cloud_project_id.map(project_url),
+        ),
+    };
+
+    let components = vec![
+        done::build(
+            done::CustomId {
+                username: verification.username.to_owned(),
+            },
+            verification.method,
+            locale,
+        ),
+        cancel::build(
+            cancel::CustomId {
+                username: verification.username.to_owned(),
+            },
+            locale,
+        ),
+        Component::Button(Button {
+            custom_id: None,
+            disabled: false,
+            emoji: None,
+            label: Some(label),
+            style: ButtonStyle::Link,
+            url,
+        }),
+    ];
+
+    let components = components
+        .into_iter()
+        .map(|component| match component {
+            Component::Button(button) => Component::Button(Button { disabled, ..button }),
+            component => component,
+        })
+        .collect();
+
+    vec![Component::ActionRow(ActionRow { components })]
+}
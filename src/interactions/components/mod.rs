@@ -1,7 +1,10 @@
+pub mod audit;
+pub mod cancel;
 pub mod code;
 pub mod done;
+pub mod flow;
 
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr, time::Instant};
 
 use base64::{display::Base64Display, engine::general_purpose::STANDARD, Engine};
 use rmp_serde::{Deserializer, Serializer};
@@ -10,7 +13,7 @@ use thiserror::Error;
 use tracing::{debug_span, Instrument};
 use twilight_model::http::interaction::InteractionResponse;
 
-use crate::{locales::Locale, state::AppState};
+use crate::{locales::Locale, metrics::metrics, state::AppState};
 
 use super::{context::MessageComponentInteraction, InteractionError};
 
@@ -29,8 +32,17 @@ pub async fn router(
         channel = ?interaction.channel_id.map(|v| v.get()),
     );
 
-    async move {
+    let name = custom_id.name();
+    let start = Instant::now();
+
+    let result = async move {
         match custom_id {
+            ComponentCustomId::Audit(custom_id) => {
+                audit::run(state, interaction, custom_id, locale).await
+            }
+            ComponentCustomId::Cancel(custom_id) => {
+                cancel::run(state, interaction, custom_id, locale).await
+            }
             ComponentCustomId::Code(custom_id) => {
                 code::run(state, interaction, custom_id, locale).await
             }
@@ -40,15 +52,33 @@ pub async fn router(
         }
     }
     .instrument(span)
-    .await
+    .await;
+
+    metrics().interaction("component", name, result.is_ok(), start.elapsed());
+
+    result
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 enum ComponentCustomId {
+    Audit(audit::CustomId),
+    Cancel(cancel::CustomId),
     Code(code::CustomId),
     Done(done::CustomId),
 }
 
+impl ComponentCustomId {
+    /// Without the data, to use as a metrics label.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Audit(_) => "audit",
+            Self::Cancel(_) => "cancel",
+            Self::Code(_) => "code",
+            Self::Done(_) => "done",
+        }
+    }
+}
+
 impl Display for ComponentCustomId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut buf = Vec::new();
@@ -85,15 +115,11 @@ impl Into<Option<String>> for ComponentCustomId {
 
 #[cfg(test)]
 mod tests {
-    use time::macros::datetime;
-
     use super::*;
 
     #[test]
     fn serde_done() {
         let original = ComponentCustomId::Done(done::CustomId {
-            code: "code".into(),
-            generated: datetime!(2023-06-18 15:35:34 UTC),
             username: "username".into(),
         });
 
@@ -102,4 +128,40 @@ mod tests {
 
         assert_eq!(original, deserialized);
     }
+
+    #[test]
+    fn serde_code_before_methods() {
+        #[derive(Serialize)]
+        struct OldCode {
+            username: String,
+            id: twilight_model::id::Id<twilight_model::id::marker::UserMarker>,
+        }
+
+        // Same variants as `ComponentCustomId`, with the code button as it was before methods
+        #[derive(Serialize)]
+        #[allow(dead_code)]
+        enum OldCustomId {
+            Audit(audit::CustomId),
+            Cancel(cancel::CustomId),
+            Code(OldCode),
+            Done(done::CustomId),
+        }
+
+        let mut buf = Vec::new();
+        OldCustomId::Code(OldCode {
+            username: "username".into(),
+            id: "755497867606622450".parse().unwrap(),
+        })
+        .serialize(&mut Serializer::new(&mut buf))
+        .unwrap();
+
+        let deserialized: ComponentCustomId = STANDARD.encode(buf).parse().unwrap();
+
+        let ComponentCustomId::Code(custom_id) = deserialized else {
+            panic!("not a code button: {deserialized:?}");
+        };
+
+        assert_eq!(custom_id.username, "username");
+        assert_eq!(custom_id.method, crate::verification::Method::Comment);
+    }
 }
@@ -8,7 +8,10 @@ use twilight_model::{
         modal::ModalInteractionData,
         Interaction, InteractionData,
     },
-    id::{marker::UserMarker, Id},
+    id::{
+        marker::{AttachmentMarker, ChannelMarker, GenericMarker, RoleMarker, UserMarker},
+        Id,
+    },
 };
 
 pub struct InteractionContext<T>(Interaction, PhantomData<T>);
@@ -68,32 +71,128 @@ pub enum CommandOptionError {
 
 pub trait GetOption<T> {
     fn get_option<'a>(&'a self, name: &str) -> Result<&'a T, CommandOptionError>;
-}
 
-impl GetOption<String> for Vec<CommandDataOption> {
-    fn get_option<'a>(&'a self, name: &str) -> Result<&'a String, CommandOptionError> {
-        match self.iter().find(|option| option.name == name) {
-            Some(option) => match &option.value {
-                CommandOptionValue::String(value) => Ok(value),
-                _ => Err(CommandOptionError::WrongType(name.to_string(), "String")),
-            },
-            None => Err(CommandOptionError::NotFound(name.to_string())),
+    /// `None` if the option wasn't given, but still an error if it has the wrong type.
+    fn get_optional<'a>(&'a self, name: &str) -> Result<Option<&'a T>, CommandOptionError> {
+        match self.get_option(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(CommandOptionError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
         }
     }
 }
 
-impl GetOption<Id<UserMarker>> for Vec<CommandDataOption> {
-    fn get_option<'a>(&'a self, name: &str) -> Result<&'a Id<UserMarker>, CommandOptionError> {
-        match self.iter().find(|option| option.name == name) {
-            Some(option) => match &option.value {
-                CommandOptionValue::User(value) => Ok(value),
-                _ => Err(CommandOptionError::WrongType(name.to_string(), "String")),
-            },
-            None => Err(CommandOptionError::NotFound(name.to_string())),
+/// A single option in a struct declared with [`command_options!`].
+pub trait FromOption: Sized {
+    fn from_option(options: &[CommandDataOption], name: &str) -> Result<Self, CommandOptionError>;
+}
+
+impl<T: FromOption> FromOption for Option<T> {
+    fn from_option(options: &[CommandDataOption], name: &str) -> Result<Self, CommandOptionError> {
+        match T::from_option(options, name) {
+            Ok(value) => Ok(Some(value)),
+            Err(CommandOptionError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
         }
     }
 }
 
+/// All options of a command or subcommand, declared with [`command_options!`].
+pub trait FromOptions: Sized {
+    fn from_options(options: &[CommandDataOption]) -> Result<Self, CommandOptionError>;
+}
+
+macro_rules! option_type {
+    ($type:ty, $variant:ident, $name:literal) => {
+        impl GetOption<$type> for [CommandDataOption] {
+            fn get_option<'a>(&'a self, name: &str) -> Result<&'a $type, CommandOptionError> {
+                match self.iter().find(|option| option.name == name) {
+                    Some(option) => match &option.value {
+                        CommandOptionValue::$variant(value) => Ok(value),
+                        _ => Err(CommandOptionError::WrongType(name.to_string(), $name)),
+                    },
+                    None => Err(CommandOptionError::NotFound(name.to_string())),
+                }
+            }
+        }
+
+        impl FromOption for $type {
+            fn from_option(
+                options: &[CommandDataOption],
+                name: &str,
+            ) -> Result<Self, CommandOptionError> {
+                GetOption::<$type>::get_option(options, name).cloned()
+            }
+        }
+    };
+}
+
+option_type!(String, String, "String");
+option_type!(i64, Integer, "Integer");
+option_type!(f64, Number, "Number");
+option_type!(bool, Boolean, "Boolean");
+option_type!(Id<UserMarker>, User, "User");
+option_type!(Id<ChannelMarker>, Channel, "Channel");
+option_type!(Id<RoleMarker>, Role, "Role");
+option_type!(Id<GenericMarker>, Mentionable, "Mentionable");
+option_type!(Id<AttachmentMarker>, Attachment, "Attachment");
+
+/// Declares a struct with a field for every option and implements [`FromOptions`] for it.
+/// Options are named after the fields, unless renamed with `#[option = "name"]`.
+/// `Option<T>` fields are `None` when the option isn't given.
+///
+/// ```ignore
+/// command_options! {
+///     struct AddOptions {
+///         role: Id<RoleMarker>,
+///         #[option = "min-value"]
+///         value: Option<i64>,
+///     }
+/// }
+///
+/// let options = AddOptions::from_options(options)?;
+/// ```
+macro_rules! command_options {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[option = $option:literal])?
+                $field:ident: $type:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $name {
+            $(pub $field: $type,)*
+        }
+
+        impl $crate::interactions::context::FromOptions for $name {
+            fn from_options(
+                options: &[twilight_model::application::interaction::application_command::CommandDataOption],
+            ) -> Result<Self, $crate::interactions::context::CommandOptionError> {
+                Ok(Self {
+                    $(
+                        $field: $crate::interactions::context::FromOption::from_option(
+                            options,
+                            $crate::interactions::context::command_options!(@name $field $($option)?),
+                        )?,
+                    )*
+                })
+            }
+        }
+    };
+    (@name $field:ident $option:literal) => {
+        $option
+    };
+    (@name $field:ident) => {
+        stringify!($field)
+    };
+}
+
+pub(crate) use command_options;
+
 pub trait GetSubcommand {
     fn get_subcommand<'a>(
         &'a self,
@@ -134,3 +233,125 @@ impl GetSubcommand for Vec<CommandDataOption> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(name: &str, value: CommandOptionValue) -> CommandDataOption {
+        CommandDataOption {
+            name: name.into(),
+            value,
+        }
+    }
+
+    fn options() -> Vec<CommandDataOption> {
+        vec![
+            option("username", CommandOptionValue::String("PMJ_Studio".into())),
+            option("user", CommandOptionValue::User(Id::new(1))),
+            option("followers", CommandOptionValue::Integer(100)),
+            option("ratio", CommandOptionValue::Number(0.5)),
+            option("csv", CommandOptionValue::Boolean(true)),
+            option("channel", CommandOptionValue::Channel(Id::new(2))),
+            option("role", CommandOptionValue::Role(Id::new(3))),
+            option("target", CommandOptionValue::Mentionable(Id::new(4))),
+            option("file", CommandOptionValue::Attachment(Id::new(5))),
+        ]
+    }
+
+    #[test]
+    fn get_option() {
+        let options = options();
+
+        let username: &String = options.get_option("username").unwrap();
+        let user: &Id<UserMarker> = options.get_option("user").unwrap();
+        let followers: &i64 = options.get_option("followers").unwrap();
+        let ratio: &f64 = options.get_option("ratio").unwrap();
+        let csv: &bool = options.get_option("csv").unwrap();
+        let channel: &Id<ChannelMarker> = options.get_option("channel").unwrap();
+        let role: &Id<RoleMarker> = options.get_option("role").unwrap();
+        let target: &Id<GenericMarker> = options.get_option("target").unwrap();
+        let file: &Id<AttachmentMarker> = options.get_option("file").unwrap();
+
+        assert_eq!(username, "PMJ_Studio");
+        assert_eq!(user.get(), 1);
+        assert_eq!(*followers, 100);
+        assert_eq!(*ratio, 0.5);
+        assert!(*csv);
+        assert_eq!(channel.get(), 2);
+        assert_eq!(role.get(), 3);
+        assert_eq!(target.get(), 4);
+        assert_eq!(file.get(), 5);
+    }
+
+    #[test]
+    fn wrong_type() {
+        let options = options();
+
+        let result: Result<&Id<UserMarker>, _> = options.get_option("username");
+
+        assert!(matches!(
+            result,
+            Err(CommandOptionError::WrongType(name, "User")) if name == "username"
+        ));
+    }
+
+    #[test]
+    fn get_optional() {
+        let options = options();
+
+        let missing: Option<&bool> = options.get_optional("missing").unwrap();
+        let wrong_type: Result<Option<&bool>, _> = options.get_optional("username");
+
+        assert_eq!(missing, None);
+        assert!(matches!(
+            wrong_type,
+            Err(CommandOptionError::WrongType(_, "Boolean"))
+        ));
+    }
+
+    command_options! {
+        struct TestOptions {
+            username: String,
+            #[option = "channel"]
+            log_channel: Id<ChannelMarker>,
+            followers: Option<i64>,
+            missing: Option<bool>,
+        }
+    }
+
+    #[test]
+    fn from_options() {
+        let options = TestOptions::from_options(&options()).unwrap();
+
+        assert_eq!(
+            options,
+            TestOptions {
+                username: "PMJ_Studio".into(),
+                log_channel: Id::new(2),
+                followers: Some(100),
+                missing: None,
+            }
+        );
+    }
+
+    #[test]
+    fn from_options_missing() {
+        let options = vec![option("followers", CommandOptionValue::Integer(1))];
+
+        let result = TestOptions::from_options(&options);
+
+        assert!(matches!(result, Err(CommandOptionError::NotFound(name)) if name == "username"));
+    }
+
+    #[test]
+    fn from_subcommand() {
+        let options = vec![option("add", CommandOptionValue::SubCommand(options()))];
+
+        let (subcommand, options) = options.get_subcommand().unwrap();
+        let options = TestOptions::from_options(options).unwrap();
+
+        assert_eq!(subcommand, "add");
+        assert_eq!(options.followers, Some(100));
+    }
+}
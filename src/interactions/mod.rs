@@ -1,8 +1,13 @@
+#[cfg(test)]
+mod tests;
+
 mod commands;
 mod components;
 mod context;
 pub mod register;
 
+pub use components::flow::FlowMessage;
+
 use axum::{
     body::Body,
     extract::State,
@@ -19,7 +24,9 @@ use twilight_model::{
     http::interaction::{InteractionResponse, InteractionResponseType},
 };
 
-use crate::{scratch::ScratchAPIError, state::AppState};
+use crate::{
+    metrics::metrics, nicknames::NicknameError, scratch::ScratchAPIError, state::AppState,
+};
 
 use self::{components::CustomIdError, context::CommandOptionError};
 
@@ -68,6 +75,12 @@ pub enum InteractionError {
     Database(#[from] sqlx::Error),
     #[error(transparent)]
     EmbedValidation(#[from] twilight_validate::embed::EmbedValidationError),
+    #[error(transparent)]
+    TwilightHttp(#[from] twilight_http::Error),
+    #[error(transparent)]
+    DeserializeBody(#[from] twilight_http::response::DeserializeBodyError),
+    #[error(transparent)]
+    Nickname(#[from] NicknameError),
 }
 
 pub async fn interaction_handler(
@@ -97,7 +110,10 @@ pub async fn interaction_handler(
             vec![timestamp.as_bytes(), &body_bytes].concat().as_ref(),
             &signature,
         )
-        .map_err(|_| InteractionHandlerError::InvalidSignature)?;
+        .map_err(|_| {
+            metrics().signature_failures.inc();
+            InteractionHandlerError::InvalidSignature
+        })?;
 
     let interaction = serde_json::from_slice::<Interaction>(&body_bytes)?;
 
@@ -111,6 +127,7 @@ async fn router(
     state: AppState,
 ) -> Result<InteractionResponse, InteractionError> {
     debug!("{:?}", interaction);
+
     let locale = interaction.locale.clone().into();
 
     match interaction.kind {
@@ -119,7 +136,20 @@ async fn router(
             data: None,
         }),
         InteractionType::ApplicationCommand => {
-            commands::router(state, interaction.into(), locale).await
+            // Only commands use the server's settings, so other interactions don't wait for them
+            let guild_config = state.guild_config(interaction.guild_id).await?;
+
+            // The server's locale override takes precedence over the user's locale
+            let locale = guild_config
+                .as_ref()
+                .and_then(|guild_config| guild_config.locale.clone())
+                .or_else(|| interaction.locale.clone())
+                .into();
+
+            commands::router(state, interaction.into(), locale, guild_config).await
+        }
+        InteractionType::ApplicationCommandAutocomplete => {
+            commands::autocomplete(state, interaction.into(), locale).await
         }
         InteractionType::MessageComponent => {
             components::router(state, interaction.into(), locale).await
@@ -1,7 +1,12 @@
 use thiserror::Error;
+use tracing::{debug, info};
 use twilight_http::{response::DeserializeBodyError, Client, Error as TwilightHttpError};
+use twilight_model::{
+    application::command::{Command, CommandOption},
+    id::{marker::GuildMarker, Id},
+};
 
-use super::commands::{about, find, link, ping, project, user};
+use super::commands::COMMANDS;
 
 #[derive(Error, Debug)]
 pub enum RegisterCommandsError {
@@ -11,23 +16,158 @@ pub enum RegisterCommandsError {
     TwilightHttp(#[from] TwilightHttpError),
 }
 
-pub async fn register_commands(token: String) -> Result<(), RegisterCommandsError> {
+fn commands() -> Vec<Command> {
+    COMMANDS.iter().map(|command| command.register()).collect()
+}
+
+/// Registers the commands globally, or only in the dev guild if set,
+/// where they show up immediately. Skipped if nothing changed.
+pub async fn register_commands(
+    token: String,
+    dev_guild_id: Option<Id<GuildMarker>>,
+) -> Result<(), RegisterCommandsError> {
     let client = Client::new(token);
     let application = client.current_user_application().await?.model().await?;
     let interaction_client = client.interaction(application.id);
 
-    interaction_client
-        .set_global_commands(&[
-            about::register(),
-            find::register(),
-            link::register(),
-            ping::register(),
-            project::register(),
-            user::register(),
-        ])
-        .await?
-        .model()
-        .await?;
+    let new_commands = commands();
+
+    let old_commands = match dev_guild_id {
+        Some(guild_id) => {
+            interaction_client
+                .guild_commands(guild_id)
+                .with_localizations(true)
+                .await?
+                .model()
+                .await?
+        }
+        None => {
+            interaction_client
+                .global_commands()
+                .with_localizations(true)
+                .await?
+                .model()
+                .await?
+        }
+    };
+
+    let guild = dev_guild_id.is_some();
+    if normalize(old_commands, guild) == normalize(new_commands.clone(), guild) {
+        debug!("commands didn't change");
+        return Ok(());
+    }
+
+    match dev_guild_id {
+        Some(guild_id) => {
+            info!("registering commands in dev guild {guild_id}");
+            interaction_client
+                .set_guild_commands(guild_id, &new_commands)
+                .await?
+                .model()
+                .await?;
+        }
+        None => {
+            info!("registering global commands");
+            interaction_client
+                .set_global_commands(&new_commands)
+                .await?
+                .model()
+                .await?;
+        }
+    }
 
     Ok(())
 }
+
+/// Removes the fields set by Discord and the differences between
+/// an omitted field and its default value, ordering commands by name.
+fn normalize(mut commands: Vec<Command>, guild: bool) -> Vec<Command> {
+    for command in &mut commands {
+        command.application_id = None;
+        command.guild_id = None;
+        command.id = None;
+        command.version = Id::new(1);
+        // Discord doesn't return it for guild commands, where it doesn't apply anyway
+        command.dm_permission = if guild {
+            None
+        } else {
+            Some(command.dm_permission.unwrap_or(true))
+        };
+        command.nsfw = command.nsfw.filter(|nsfw| *nsfw);
+        command.name_localizations = command.name_localizations.take().filter(|l| !l.is_empty());
+        command.description_localizations = command
+            .description_localizations
+            .take()
+            .filter(|l| !l.is_empty());
+        normalize_options(&mut command.options);
+    }
+
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    commands
+}
+
+fn normalize_options(options: &mut [CommandOption]) {
+    for option in options {
+        option.autocomplete = option.autocomplete.filter(|autocomplete| *autocomplete);
+        option.required = option.required.filter(|required| *required);
+        option.channel_types = option.channel_types.take().filter(|v| !v.is_empty());
+        option.choices = option.choices.take().filter(|v| !v.is_empty());
+        option.name_localizations = option.name_localizations.take().filter(|l| !l.is_empty());
+        option.description_localizations = option
+            .description_localizations
+            .take()
+            .filter(|l| !l.is_empty());
+
+        if let Some(options) = &mut option.options {
+            normalize_options(options);
+        }
+        option.options = option.options.take().filter(|v| !v.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged() {
+        let mut registered = commands();
+        registered.reverse();
+        for (i, command) in registered.iter_mut().enumerate() {
+            command.application_id = Some(Id::new(1));
+            command.id = Some(Id::new(i as u64 + 1));
+            command.version = Id::new(i as u64 + 100);
+            command.dm_permission.get_or_insert(true);
+            command.nsfw = Some(false);
+        }
+
+        assert_eq!(normalize(registered, false), normalize(commands(), false));
+    }
+
+    #[test]
+    fn unchanged_in_guild() {
+        let mut registered = commands();
+        for command in &mut registered {
+            command.guild_id = Some(Id::new(1));
+            command.dm_permission = None;
+        }
+
+        assert_eq!(normalize(registered, true), normalize(commands(), true));
+    }
+
+    #[test]
+    fn changed() {
+        let mut registered = commands();
+        registered[0].description = "Old description".into();
+
+        assert_ne!(normalize(registered, false), normalize(commands(), false));
+    }
+
+    #[test]
+    fn removed() {
+        let mut registered = commands();
+        registered.pop();
+
+        assert_ne!(normalize(registered, false), normalize(commands(), false));
+    }
+}
@@ -0,0 +1,103 @@
+use twilight_model::{
+    channel::message::MessageFlags, http::interaction::InteractionResponseType, id::Id,
+};
+
+use crate::{
+    database::{Database, GuildConfig},
+    locales::Locale,
+};
+
+use super::*;
+
+#[sqlx::test]
+async fn about(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .interact(&InteractionBuilder::command("about").build())
+        .await;
+
+    assert_eq!(
+        response.kind,
+        InteractionResponseType::ChannelMessageWithSource
+    );
+    assert_eq!(response.data.unwrap().content.unwrap(), "About Scratchy");
+}
+
+#[sqlx::test]
+async fn ping_localized(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let timestamp = app.state.start_time.timestamp();
+
+    let response = app
+        .interact(&InteractionBuilder::command("ping").locale("pl").build())
+        .await;
+
+    assert_eq!(
+        response.data.unwrap().content.unwrap(),
+        Locale::Pl.ping_last_restart(&timestamp)
+    );
+}
+
+#[sqlx::test]
+async fn disabled_in_guild(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let guild_id = Id::new(4);
+
+    let mut config = GuildConfig::new(guild_id);
+    config.disabled_commands = vec!["ping".into()];
+    app.state.pool.write_guild_config(&config).await.unwrap();
+
+    let response = app
+        .interact(&InteractionBuilder::command("ping").guild(4).build())
+        .await;
+
+    let data = response.data.unwrap();
+    assert_eq!(data.content.unwrap(), Locale::En.command_disabled());
+    assert_eq!(data.flags, Some(MessageFlags::EPHEMERAL));
+}
+
+#[sqlx::test]
+async fn ephemeral_in_guild(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let guild_id = Id::new(4);
+
+    let mut config = GuildConfig::new(guild_id);
+    config.ephemeral = true;
+    app.state.pool.write_guild_config(&config).await.unwrap();
+
+    let response = app
+        .interact(&InteractionBuilder::command("about").guild(4).build())
+        .await;
+
+    assert_eq!(response.data.unwrap().flags, Some(MessageFlags::EPHEMERAL));
+}
+
+#[sqlx::test]
+async fn invalid_username(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .interact(
+            &InteractionBuilder::command("user")
+                .option("username", 3, json!("not a username!"))
+                .build(),
+        )
+        .await;
+
+    assert_eq!(
+        response.data.unwrap().content.unwrap(),
+        Locale::En.invalid_username()
+    );
+}
+
+#[sqlx::test]
+async fn unknown_command(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .send(&InteractionBuilder::command("unknown").build())
+        .await;
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
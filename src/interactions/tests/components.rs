@@ -0,0 +1,101 @@
+use twilight_model::{channel::message::Component, http::interaction::InteractionResponseType};
+
+use crate::{
+    database::{link_account, Database},
+    interactions::components::{
+        cancel::{self, CustomId},
+        code,
+    },
+    locales::Locale,
+    verification::{Method, VerifiedVia},
+};
+
+use super::*;
+
+fn custom_id(component: Component) -> String {
+    match component {
+        Component::Button(button) => button.custom_id.unwrap(),
+        _ => unreachable!(),
+    }
+}
+
+#[sqlx::test]
+async fn cancel_expired(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let custom_id = custom_id(cancel::build(
+        CustomId {
+            username: "PMJ_Studio".into(),
+        },
+        Locale::En,
+    ));
+
+    let response = app
+        .interact(&InteractionBuilder::component(&custom_id).build())
+        .await;
+
+    assert_eq!(response.kind, InteractionResponseType::UpdateMessage);
+    assert_eq!(
+        response.data.unwrap().content.unwrap(),
+        Locale::En.code_expired()
+    );
+}
+
+/// Verifying the account transfers it, so the code is generated.
+#[sqlx::test]
+async fn code_linked_to_other(pool: PgPool) {
+    let app = TestApp::new(pool);
+    link_account(
+        &app.state.pool,
+        "PMJ_Studio".into(),
+        None,
+        "1".parse().unwrap(),
+        VerifiedVia::Admin,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let custom_id = custom_id(code::build(
+        code::CustomId {
+            username: "PMJ_Studio".into(),
+            id: "755497867606622450".parse().unwrap(),
+            method: Method::Comment,
+            scratch_id: None,
+        },
+        Locale::En,
+    ));
+
+    let response = app
+        .interact(&InteractionBuilder::component(&custom_id).build())
+        .await;
+
+    assert_eq!(response.kind, InteractionResponseType::UpdateMessage);
+    assert!(app
+        .state
+        .pool
+        .get_pending_verification("755497867606622450".parse().unwrap(), "PMJ_Studio".into())
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[sqlx::test]
+async fn invalid_custom_id(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .send(&InteractionBuilder::component("invalid").build())
+        .await;
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+/// There are no modals yet.
+#[sqlx::test]
+async fn modal_unsupported(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app.send(&InteractionBuilder::modal("modal").build()).await;
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
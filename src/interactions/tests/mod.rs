@@ -0,0 +1,236 @@
+//! End-to-end tests of `/interactions`, with requests signed the way Discord signs them.
+
+mod commands;
+mod components;
+mod signature;
+
+use std::collections::HashMap;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use hyper::body::to_bytes;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use tower::ServiceExt;
+use twilight_model::http::interaction::InteractionResponse;
+
+use crate::{
+    app,
+    state::{AppState, Config},
+};
+
+pub struct TestApp {
+    pub state: AppState,
+    router: Router,
+    keypair: Keypair,
+}
+
+impl TestApp {
+    /// Uses a new keypair, so that only requests signed by [`TestApp::send`] are valid.
+    pub fn new(pool: PgPool) -> Self {
+        let secret = SecretKey::from_bytes(&rand::random::<[u8; 32]>()).unwrap();
+        let public = PublicKey::from(&secret);
+        let keypair = Keypair { secret, public };
+
+        let settings = HashMap::from([
+            ("base_url", "https://example.com".to_string()),
+            ("discord_client_id", "1000".to_string()),
+            ("discord_client_secret", "secret".to_string()),
+            ("discord_public_key", hex::encode(public.as_bytes())),
+            ("discord_token", "token".to_string()),
+        ]);
+        let config = Config::new(|key| settings.get(key).cloned()).unwrap();
+
+        let state = AppState::new(config, pool);
+        let router = app::router(state.clone());
+
+        Self {
+            state,
+            router,
+            keypair,
+        }
+    }
+
+    pub fn sign(&self, timestamp: &str, body: &[u8]) -> String {
+        let message = [timestamp.as_bytes(), body].concat();
+        hex::encode(self.keypair.sign(&message).to_bytes())
+    }
+
+    pub async fn request(&self, request: Request<Body>) -> Response {
+        self.router.clone().oneshot(request).await.unwrap()
+    }
+
+    /// Sends a correctly signed interaction.
+    pub async fn send(&self, interaction: &Value) -> Response {
+        let body = serde_json::to_vec(interaction).unwrap();
+        let timestamp = OffsetDateTime::now_utc().unix_timestamp().to_string();
+        let signature = self.sign(&timestamp, &body);
+
+        self.request(interaction_request(&signature, &timestamp, body))
+            .await
+    }
+
+    /// Sends a correctly signed interaction and expects a successful response.
+    pub async fn interact(&self, interaction: &Value) -> InteractionResponse {
+        let response = self.send(interaction).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+}
+
+pub fn interaction_request(signature: &str, timestamp: &str, body: Vec<u8>) -> Request<Body> {
+    Request::post("/interactions")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-signature-ed25519", signature)
+        .header("x-signature-timestamp", timestamp)
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// Builds interaction payloads in the format sent by Discord.
+#[derive(Debug, Clone)]
+pub struct InteractionBuilder {
+    kind: u8,
+    data: Option<Value>,
+    guild_id: Option<u64>,
+    user_id: u64,
+    locale: &'static str,
+}
+
+impl InteractionBuilder {
+    fn new(kind: u8, data: Option<Value>) -> Self {
+        Self {
+            kind,
+            data,
+            guild_id: None,
+            user_id: 755497867606622450,
+            locale: "en-US",
+        }
+    }
+
+    pub fn ping() -> Self {
+        Self::new(1, None)
+    }
+
+    pub fn command(name: &str) -> Self {
+        Self::new(
+            2,
+            Some(json!({
+                "id": "1",
+                "name": name,
+                "type": 1,
+                "options": [],
+            })),
+        )
+    }
+
+    pub fn component(custom_id: &str) -> Self {
+        Self::new(
+            3,
+            Some(json!({
+                "custom_id": custom_id,
+                "component_type": 2,
+                "values": [],
+            })),
+        )
+    }
+
+    pub fn modal(custom_id: &str) -> Self {
+        Self::new(
+            5,
+            Some(json!({
+                "custom_id": custom_id,
+                "components": [],
+            })),
+        )
+    }
+
+    /// Adds a command option, `kind` is the Discord option type.
+    pub fn option(mut self, name: &str, kind: u8, value: Value) -> Self {
+        let options = self.data.as_mut().unwrap()["options"]
+            .as_array_mut()
+            .unwrap();
+        options.push(json!({ "name": name, "type": kind, "value": value }));
+        self
+    }
+
+    /// Adds a subcommand with its options, `kind` is the Discord option type.
+    pub fn subcommand(mut self, name: &str, options: Vec<(&str, u8, Value)>) -> Self {
+        let options: Vec<_> = options
+            .into_iter()
+            .map(|(name, kind, value)| json!({ "name": name, "type": kind, "value": value }))
+            .collect();
+
+        self.data.as_mut().unwrap()["options"] = json!([{
+            "name": name,
+            "type": 1,
+            "options": options,
+        }]);
+        self
+    }
+
+    pub fn guild(mut self, guild_id: u64) -> Self {
+        self.guild_id = Some(guild_id);
+        self
+    }
+
+    pub fn user(mut self, user_id: u64) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    pub fn locale(mut self, locale: &'static str) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn build(self) -> Value {
+        let user = json!({
+            "id": self.user_id.to_string(),
+            "username": "test",
+            "discriminator": "0",
+            "avatar": null,
+        });
+
+        let mut interaction = json!({
+            "id": "2",
+            "application_id": "1000",
+            "type": self.kind,
+            "token": "interaction-token",
+            "version": 1,
+            "channel_id": "3",
+            "locale": self.locale,
+        });
+
+        if let Some(data) = self.data {
+            interaction["data"] = data;
+        }
+
+        // Discord sends the member in servers and the user in DMs
+        match self.guild_id {
+            Some(guild_id) => {
+                interaction["guild_id"] = json!(guild_id.to_string());
+                interaction["member"] = json!({
+                    "user": user,
+                    "roles": [],
+                    "joined_at": "2023-01-01T00:00:00.000000+00:00",
+                    "deaf": false,
+                    "mute": false,
+                    "flags": 0,
+                    "permissions": "0",
+                });
+            }
+            None => interaction["user"] = user,
+        }
+
+        interaction
+    }
+}
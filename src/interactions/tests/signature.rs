@@ -0,0 +1,66 @@
+use twilight_model::http::interaction::InteractionResponseType;
+
+use super::*;
+
+#[sqlx::test]
+async fn ping(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app.interact(&InteractionBuilder::ping().build()).await;
+
+    assert_eq!(response.kind, InteractionResponseType::Pong);
+}
+
+#[sqlx::test]
+async fn ping_without_database(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.state.pool.close().await;
+
+    let response = app
+        .interact(&InteractionBuilder::ping().guild(4).build())
+        .await;
+
+    assert_eq!(response.kind, InteractionResponseType::Pong);
+}
+
+#[sqlx::test]
+async fn invalid_signature(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let body = serde_json::to_vec(&InteractionBuilder::ping().build()).unwrap();
+    let signature = app.sign("1", &body);
+
+    // Signed with a different timestamp
+    let response = app
+        .request(interaction_request(&signature, "2", body))
+        .await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn tampered_body(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let body = serde_json::to_vec(&InteractionBuilder::ping().build()).unwrap();
+    let signature = app.sign("1", &body);
+
+    let tampered = serde_json::to_vec(&InteractionBuilder::command("ping").build()).unwrap();
+    let response = app
+        .request(interaction_request(&signature, "1", tampered))
+        .await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn missing_headers(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let body = serde_json::to_vec(&InteractionBuilder::ping().build()).unwrap();
+
+    let request = Request::post("/interactions")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.request(request).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
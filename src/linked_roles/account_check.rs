@@ -0,0 +1,173 @@
+//! Notices when linked Scratch accounts are deleted or banned, since ScratchDB keeps old data.
+//! The check also records the stats of found accounts for `/stats history`.
+
+use time::OffsetDateTime;
+use tracing::{debug, error, info, warn};
+use twilight_http::error::ErrorType;
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    database::{Database, ScratchAccount, StatsSnapshot},
+    event_log::{self, Event},
+    locales::Locale,
+    scratch::{api::ScratchAPIClient, db::ScratchDBClient, site::user_link},
+    state::AppState,
+};
+
+/// Daily checks in a row which didn't find the account before it's considered gone,
+/// so that a short Scratch outage doesn't mark every account.
+const GONE_AFTER_CHECKS: i16 = 3;
+
+/// Checks the linked account on Scratch and notifies about it if it's newly gone.
+///
+/// Accounts are checked once a day, so this also keeps one stats snapshot a day.
+pub async fn check_account(state: &AppState, account: &ScratchAccount) -> anyhow::Result<()> {
+    let found = match state
+        .reqwest_client
+        .get_scratch_api_user(&account.username)
+        .await
+    {
+        Ok(user) => user.is_some(),
+        // Only a missing account counts, not an unavailable API
+        Err(err) => {
+            warn!(
+                "failed to check linked Scratch account {}: {err}",
+                account.username
+            );
+            return Ok(());
+        }
+    };
+
+    let Some(checked) = state
+        .pool
+        .record_scratch_account_check(account.username.to_owned(), found, GONE_AFTER_CHECKS)
+        .await?
+    else {
+        debug!("{} was unlinked before it was checked", account.username);
+        return Ok(());
+    };
+
+    // The history is only extra, so it shouldn't stop the check
+    if found {
+        if let Err(err) = record_stats(state, &checked.username).await {
+            error!("failed to record stats of {}: {err}", checked.username);
+        }
+    }
+
+    if account.gone_at.is_none() && checked.gone_at.is_some() {
+        info!(
+            "linked Scratch account {} of {} is gone",
+            checked.username, checked.id
+        );
+        notify(state, &checked).await;
+    } else if account.gone_at.is_some() && checked.gone_at.is_none() {
+        info!(
+            "linked Scratch account {} of {} is back",
+            checked.username, checked.id
+        );
+    }
+
+    Ok(())
+}
+
+/// Adds the account to the history shown by `/stats history`.
+async fn record_stats(state: &AppState, username: &str) -> anyhow::Result<()> {
+    let Some(user) = state.reqwest_client.get_scratch_db_user(username).await? else {
+        return Ok(());
+    };
+
+    // Without statistics there's nothing to chart
+    let Some(statistics) = user.statistics else {
+        return Ok(());
+    };
+
+    state
+        .pool
+        .write_stats_snapshot(&StatsSnapshot {
+            username: user.username,
+            recorded_at: OffsetDateTime::now_utc(),
+            followers: statistics.followers,
+            status: user.status,
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Sends a DM if enabled with `notify_gone_accounts`, and logs the event in the servers
+/// of the owner which have a log channel.
+async fn notify(state: &AppState, account: &ScratchAccount) {
+    if state.config.notify_gone_accounts {
+        if let Err(err) = send_dm(state, account).await {
+            warn!(
+                "failed to notify {} about a gone account: {err}",
+                account.id
+            );
+        }
+    }
+
+    let guild_ids = match state.pool.get_guilds_with_log_channel().await {
+        Ok(guild_ids) => guild_ids,
+        Err(err) => {
+            error!("{}", err);
+            return;
+        }
+    };
+
+    for guild_id in guild_ids {
+        match is_member(state, guild_id, account.id).await {
+            Ok(true) => event_log::log(
+                state,
+                Some(guild_id),
+                Event::AccountGone {
+                    id: account.id,
+                    username: account.username.to_owned(),
+                },
+            ),
+            Ok(false) => {}
+            Err(err) => error!("failed to check if {} is in {guild_id}: {err}", account.id),
+        }
+    }
+}
+
+async fn send_dm(state: &AppState, account: &ScratchAccount) -> anyhow::Result<()> {
+    let channel = state
+        .discord_client
+        .create_private_channel(account.id)
+        .await?
+        .model()
+        .await?;
+
+    // There's no locale outside of interactions
+    let content = Locale::default().account_gone(&user_link(&account.username));
+
+    state
+        .discord_client
+        .create_message(channel.id)
+        .content(&content)?
+        .await?;
+
+    debug!(
+        "notified {} about gone account {}",
+        account.id, account.username
+    );
+
+    Ok(())
+}
+
+async fn is_member(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    id: Id<UserMarker>,
+) -> Result<bool, twilight_http::Error> {
+    match state.discord_client.guild_member(guild_id, id).await {
+        Ok(_) => Ok(true),
+        Err(err) if matches!(err.kind(), ErrorType::Response { status, .. } if status.get() == 404) => {
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
@@ -1,5 +1,9 @@
-use std::time::Duration;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
+use serde::Serialize;
 use time::OffsetDateTime;
 use tokio::{
     task::JoinHandle,
@@ -7,9 +11,50 @@ use tokio::{
 };
 use tracing::{debug, error, info};
 
-use crate::{database::Database, state::AppState};
+use crate::{database::Database, metrics::metrics, roles, state::AppState};
+
+use super::{account_check::check_account, RoleConnectionUpdater};
+
+/// Between checks of linked accounts, to stay well below the Scratch API rate limits.
+const CHECK_DELAY: Duration = Duration::from_secs(3);
+
+/// How long snapshots for `/stats history` are kept, more than it shows at once.
+const STATS_RETENTION: time::Duration = time::Duration::days(90);
+
+/// Progress of the background updater, shared with the health endpoints.
+#[derive(Debug, Clone, Default)]
+pub struct UpdaterStatus(Arc<RwLock<UpdaterProgress>>);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdaterProgress {
+    /// Updated at least every minute while the updater is alive
+    #[serde(with = "time::serde::timestamp::option")]
+    pub heartbeat: Option<OffsetDateTime>,
+    pub running: bool,
+    #[serde(with = "time::serde::timestamp::option")]
+    pub started_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::timestamp::option")]
+    pub finished_at: Option<OffsetDateTime>,
+    /// Of the current run, or the last one if not running
+    pub successful: u64,
+    pub failed: u64,
+}
+
+impl UpdaterStatus {
+    pub fn progress(&self) -> UpdaterProgress {
+        self.0.read().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut UpdaterProgress)) {
+        let mut progress = self.0.write().unwrap();
+        f(&mut progress);
+        progress.heartbeat = Some(OffsetDateTime::now_utc());
+    }
 
-use super::RoleConnectionUpdater;
+    fn beat(&self) {
+        self.update(|_| {});
+    }
+}
 
 pub fn spawn(state: AppState) -> JoinHandle<()> {
     tokio::spawn(background_updater(state))
@@ -19,15 +64,37 @@ async fn background_updater(state: AppState) -> () {
     debug!("starting background updater");
 
     let start_time = OffsetDateTime::now_utc().time();
-    let mut day = interval(Duration::from_secs(60 * 60 * 24));
-    day.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut next_run = OffsetDateTime::now_utc();
+
+    // Ticks while waiting for the next run, so that the heartbeat doesn't go stale
+    let mut minute = interval(Duration::from_secs(60));
+    minute.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
     loop {
-        day.tick().await;
+        minute.tick().await;
+        state.updater.beat();
+
+        let now = OffsetDateTime::now_utc();
+        if now < next_run {
+            continue;
+        }
+        while next_run <= now {
+            next_run += time::Duration::DAY;
+        }
 
         info!("starting today's background metadata update");
 
-        let today = OffsetDateTime::now_utc().replace_time(start_time);
+        let today = now.replace_time(start_time);
+
+        state.updater.update(|progress| {
+            progress.running = true;
+            progress.started_at = Some(now);
+            progress.successful = 0;
+            progress.failed = 0;
+        });
+
+        // Gone accounts are left out of the metadata, so they are checked first
+        check_accounts(&state, today).await;
 
         let mut delay = interval(Duration::from_secs(10));
         // Ensure at least 10 seconds for every batch of ScratchDB calls
@@ -41,28 +108,92 @@ async fn background_updater(state: AppState) -> () {
         // Records added today will be alredy up to date on creation
         loop {
             match update_next_metadata(&state, today).await {
-                Ok(Some(())) => successful += 1,
+                Ok(Some(())) => {
+                    metrics().updater_update(true);
+                    successful += 1;
+                }
                 Ok(None) => break,
                 Err(err) => {
                     error!("{}", err);
+                    metrics().updater_update(false);
                     failed += 1;
                 }
             }
 
+            state.updater.update(|progress| {
+                progress.successful = successful;
+                progress.failed = failed;
+            });
+
             delay.tick().await;
         }
 
+        // After the metadata, so that it can be reused for members who authorized Linked Roles
+        if let Err(err) = roles::sync_all(&state).await {
+            error!("failed to update roles: {err}");
+        }
+
+        match state
+            .pool
+            .delete_stats_snapshots_before(now - STATS_RETENTION)
+            .await
+        {
+            Ok(0) => {}
+            Ok(deleted) => debug!("deleted {deleted} old stats snapshots"),
+            Err(err) => error!("failed to delete old stats snapshots: {err}"),
+        }
+
+        state.updater.update(|progress| {
+            progress.running = false;
+            progress.finished_at = Some(OffsetDateTime::now_utc());
+        });
+
         info!(
             "updated metadata ({successful} successful, {failed} failed), waiting until tomorrow",
         );
     }
 }
 
+/// Checks every linked account which wasn't checked since `today` on Scratch, also the ones of
+/// users who never authorized Linked Roles.
+///
+/// Accounts are checked at most once a day, so that a restart doesn't count as another day.
+async fn check_accounts(state: &AppState, today: OffsetDateTime) {
+    let accounts = match state.pool.get_scratch_accounts_to_check(today).await {
+        Ok(accounts) => accounts,
+        Err(err) => {
+            error!("failed to get linked accounts to check: {err}");
+            return;
+        }
+    };
+
+    info!("checking {} linked Scratch accounts", accounts.len());
+
+    let mut delay = interval(CHECK_DELAY);
+    delay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    for account in accounts {
+        delay.tick().await;
+        state.updater.beat();
+
+        if let Err(err) = check_account(state, &account).await {
+            error!(
+                "failed to check linked Scratch account {}: {err}",
+                account.username
+            );
+        }
+    }
+}
+
 async fn update_next_metadata(
     state: &AppState,
     today: OffsetDateTime,
 ) -> anyhow::Result<Option<()>> {
     if let Some((id, updated_at)) = state.pool.get_oldest_metadata().await? {
+        metrics()
+            .updater_lag
+            .set((OffsetDateTime::now_utc() - updated_at).as_seconds_f64());
+
         if today > updated_at {
             debug!("updating role connection metadata for {id}");
             state.update_role_connection(id).await?;
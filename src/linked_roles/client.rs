@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use hyper::header::AUTHORIZATION;
 use oauth2::{basic::BasicClient, AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
@@ -56,6 +58,13 @@ pub trait RoleConnectionClient {
         token: &str,
         data: &RoleConnection<Self::Data>,
     ) -> Result<RoleConnection<Self::Data>, Self::Error>;
+
+    /// Removes the metadata values, so that the user doesn't qualify for any linked role.
+    async fn clear_role_connection(
+        &self,
+        client_id: &str,
+        token: &str,
+    ) -> Result<RoleConnection, Self::Error>;
 }
 
 fn metadata_url(client_id: &str) -> String {
@@ -130,4 +139,25 @@ impl RoleConnectionClient for Client {
             .json()
             .await
     }
+
+    async fn clear_role_connection(
+        &self,
+        client_id: &str,
+        token: &str,
+    ) -> Result<RoleConnection, Self::Error> {
+        let data = RoleConnection {
+            platform_name: None,
+            platform_username: None,
+            metadata: HashMap::new(),
+        };
+
+        self.put(&role_connection_url(client_id))
+            .bearer_auth(token)
+            .json(&data)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
 }
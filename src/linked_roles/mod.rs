@@ -1,3 +1,4 @@
+mod account_check;
 mod background_updater;
 mod client;
 mod metadata;
@@ -8,10 +9,10 @@ mod token;
 mod token_client;
 mod update;
 
-pub use background_updater::spawn as spawn_background_updater;
+pub use background_updater::{spawn as spawn_background_updater, UpdaterProgress, UpdaterStatus};
 pub use client::create_oauth_client;
 pub use metadata::RoleConnectionData;
 pub use register::register_metadata;
 pub use router::router;
 pub use token::{OAuthToken, Token};
-pub use update::RoleConnectionUpdater;
+pub use update::{fetch_role_connection, save_metadata, RoleConnectionUpdater};
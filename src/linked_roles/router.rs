@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use axum::{
     extract::{Query, State},
     response::{IntoResponse, Redirect},
@@ -12,11 +13,12 @@ use oauth2::{
 };
 use serde::Deserialize;
 use sqlx::PgPool;
+use tracing::error;
 use twilight_http::Client as TwilightClient;
 
-use crate::{database::Database, state::AppState};
+use crate::{database::Database, metrics::metrics, state::AppState};
 
-use super::update::RoleConnectionUpdater;
+use super::{update::RoleConnectionUpdater, Token};
 
 static COOKIE_NAME: &str = "oauth_state";
 
@@ -61,37 +63,50 @@ async fn callback(
     State(state): State<AppState>,
     jar: CookieJar,
 ) -> impl IntoResponse {
-    let cookie_state = jar.get(COOKIE_NAME).unwrap().value();
-    if query.state != cookie_state {
+    let cookie_state = jar.get(COOKIE_NAME).map(|cookie| cookie.value());
+    if cookie_state != Some(query.state.as_str()) {
+        metrics().oauth_callback("invalid_state");
         return StatusCode::FORBIDDEN;
     }
 
-    let token = oauth_client
-        .exchange_code(AuthorizationCode::new(query.code))
-        .request_async(async_http_client)
-        .await
-        .unwrap();
+    let result = async {
+        let token = oauth_client
+            .exchange_code(AuthorizationCode::new(query.code))
+            .request_async(async_http_client)
+            .await?;
 
-    let access_token = format!("Bearer {}", token.access_token().secret());
-    let discord_client = TwilightClient::new(access_token);
+        let access_token = format!("Bearer {}", token.access_token().secret());
+        let discord_client = TwilightClient::new(access_token);
 
-    let current_authorization = discord_client
-        .current_authorization()
-        .await
-        .unwrap()
-        .model()
-        .await
-        .unwrap();
+        let current_authorization = discord_client
+            .current_authorization()
+            .await?
+            .model()
+            .await?;
 
-    // Safe to unwrap because we always request the `identify` scope
-    let id = current_authorization.user.unwrap().id;
+        // Safe to unwrap because we always request the `identify` scope
+        let id = current_authorization.user.unwrap().id;
 
-    // Safe to unwrap because we assume Discord returns all the necessary fields
-    pool.write_token(id, token.try_into().unwrap())
-        .await
-        .unwrap(); // TODO: don't
+        let token: Token = token
+            .try_into()
+            .map_err(|_| anyhow!("token response is missing expires_in"))?;
+        pool.write_token(id, token).await?;
 
-    state.update_role_connection(id).await.unwrap();
+        state.update_role_connection(id).await?;
 
-    StatusCode::OK
+        anyhow::Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            metrics().oauth_callback("ok");
+            StatusCode::OK
+        }
+        Err(err) => {
+            error!("OAuth callback failed: {err}");
+            metrics().oauth_callback("error");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
@@ -4,7 +4,7 @@ use tokio::task::JoinSet;
 use twilight_model::id::{marker::UserMarker, Id};
 
 use crate::{
-    database::{Database, ScratchAccount},
+    database::{Database, DatabaseTransaction, ScratchAccount, Transactional},
     scratch::{
         db::{self, user::Status, ScratchDBClient},
         ScratchAPIError,
@@ -23,10 +23,11 @@ use super::{
 pub trait RoleConnectionUpdater {
     type Error;
 
+    /// `None` if none of the linked accounts count, the metadata is then cleared.
     async fn update_role_connection(
         &self,
         id: Id<UserMarker>,
-    ) -> Result<RoleConnection<RoleConnectionData>, Self::Error>;
+    ) -> Result<Option<RoleConnection<RoleConnectionData>>, Self::Error>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -41,8 +42,6 @@ pub enum RoleConnectionUpdateError {
     ScratchAPIError(#[from] ScratchAPIError),
     #[error(transparent)]
     ReqwestError(#[from] reqwest::Error),
-    #[error("no accounts found in ScratchDB for user {0}")]
-    NoAccountsFound(Id<UserMarker>),
 }
 
 #[async_trait]
@@ -52,40 +51,97 @@ impl RoleConnectionUpdater for AppState {
     async fn update_role_connection(
         &self,
         id: Id<UserMarker>,
-    ) -> Result<RoleConnection<RoleConnectionData>, Self::Error> {
+    ) -> Result<Option<RoleConnection<RoleConnectionData>>, Self::Error> {
         let token = self.get_active_token(id).await?;
 
-        let mut tx = self.pool.begin().await?;
+        let (role_connection, changed) =
+            update_metadata(&self.pool, &self.reqwest_client, id).await?;
 
-        let linked_accounts = tx.get_linked_scratch_accounts(id).await?;
-
-        let accounts = fetch_scratch_data(linked_accounts, &self.reqwest_client).await?;
-        if accounts.len() == 0 {
-            return Err(RoleConnectionUpdateError::NoAccountsFound(id));
+        // Only update if the metadata has changed or was `None`
+        if changed {
+            match &role_connection {
+                Some(role_connection) => {
+                    self.reqwest_client
+                        .put_role_connection(
+                            &self.config.client_id,
+                            &token.access_token,
+                            role_connection,
+                        )
+                        .await?;
+                }
+                // Otherwise Discord would keep the values of accounts which don't count anymore
+                None => {
+                    self.reqwest_client
+                        .clear_role_connection(&self.config.client_id, &token.access_token)
+                        .await?;
+                }
+            }
         }
 
-        let role_connection = find_metadata_values(accounts);
+        Ok(role_connection)
+    }
+}
 
-        let old_data = tx.get_metadata(id).await?;
+/// Calculates the metadata of the user's linked accounts and saves it,
+/// returning it with whether it changed or wasn't set before.
+///
+/// Without any accounts which count, the metadata is cleared, so that the update isn't
+/// retried and the old values don't grant roles anymore.
+async fn update_metadata<P>(
+    pool: &P,
+    client: &Client,
+    id: Id<UserMarker>,
+) -> Result<(Option<RoleConnection<RoleConnectionData>>, bool), RoleConnectionUpdateError>
+where
+    P: Transactional,
+    for<'a> &'a P: Database<Error = sqlx::Error>,
+{
+    let linked_accounts = pool.get_linked_scratch_accounts(id).await?;
+
+    let accounts = fetch_scratch_data(linked_accounts, client).await?;
+    if accounts.is_empty() {
+        let changed = save_metadata(pool, id, None).await?;
+        return Ok((None, changed));
+    }
 
-        // Write even if unchanged to update `updated_at`
-        tx.write_metadata(id, &role_connection.metadata).await?;
+    let role_connection = find_metadata_values(accounts);
 
-        tx.commit().await?;
+    let changed = save_metadata(pool, id, Some(&role_connection.metadata)).await?;
 
-        // Only update if the metadata has changed or was `None`
-        if old_data.as_ref() != Some(&role_connection.metadata) {
-            self.reqwest_client
-                .put_role_connection(
-                    &self.config.client_id,
-                    &token.access_token,
-                    &role_connection,
-                )
-                .await?;
-        }
+    Ok((Some(role_connection), changed))
+}
 
-        Ok(role_connection)
+/// Writes the metadata, or clears it with `None`,
+/// returning whether it changed or wasn't set before.
+pub async fn save_metadata<P: Transactional>(
+    pool: &P,
+    id: Id<UserMarker>,
+    data: Option<&RoleConnectionData>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.transaction().await?;
+
+    let old_data = tx.executor().get_metadata(id).await?;
+
+    // Write even if unchanged to update `updated_at`
+    tx.executor().write_metadata(id, data).await?;
+
+    tx.commit().await?;
+
+    Ok(old_data.as_ref() != data)
+}
+
+/// Calculates the metadata values from ScratchDB, or `None` if none of the accounts are in ScratchDB
+/// or all of them are gone.
+pub async fn fetch_role_connection(
+    linked_accounts: Vec<ScratchAccount>,
+    client: &Client,
+) -> Result<Option<RoleConnection<RoleConnectionData>>, ScratchAPIError> {
+    let accounts = fetch_scratch_data(linked_accounts, client).await?;
+    if accounts.len() == 0 {
+        return Ok(None);
     }
+
+    Ok(Some(find_metadata_values(accounts)))
 }
 
 async fn fetch_scratch_data(
@@ -96,7 +152,11 @@ async fn fetch_scratch_data(
 
     let mut set = JoinSet::new();
 
-    for account in linked_accounts {
+    // Gone accounts stay linked, but don't count until they're found again
+    for account in linked_accounts
+        .into_iter()
+        .filter(|account| account.gone_at.is_none())
+    {
         let client = client.clone();
         set.spawn(async move { client.get_scratch_db_user(&account.username).await });
     }
@@ -1,80 +1,46 @@
+mod app;
+mod audit;
+mod backfill;
+mod card;
 mod database;
 mod embeds;
+mod event_log;
+mod health;
 mod interactions;
 mod linked_roles;
 mod locales;
+mod metrics;
+mod nicknames;
+mod roles;
 mod scratch;
+#[cfg(feature = "standalone")]
+mod standalone;
 mod state;
+mod verification;
 
-use axum::{
-    routing::{get, post},
-    Router,
-};
-use linked_roles::spawn_background_updater;
-use shuttle_secrets::SecretStore;
-
-use interactions::{interaction_handler, register::register_commands};
-use sqlx::PgPool;
-use state::AppState;
-use tower_http::trace::TraceLayer;
-use tracing::{debug, error, info, trace, warn};
-use tracing_panic::panic_hook;
-
-use crate::linked_roles::register_metadata;
-
-async fn hello_world() -> &'static str {
-    "Hello, world!"
-}
+#[cfg(not(any(feature = "shuttle", feature = "standalone")))]
+compile_error!("enable either the `shuttle` or the `standalone` feature");
 
+#[cfg(feature = "shuttle")]
 #[shuttle_runtime::main]
 async fn axum(
-    #[shuttle_secrets::Secrets] secrets: SecretStore,
-    #[shuttle_aws_rds::Postgres(local_uri = "{secrets.database_url}")] pool: PgPool,
+    #[shuttle_secrets::Secrets] secrets: shuttle_secrets::SecretStore,
+    #[shuttle_aws_rds::Postgres(local_uri = "{secrets.database_url}")] pool: sqlx::PgPool,
 ) -> shuttle_axum::ShuttleAxum {
-    tracing_subscriber::fmt()
-        .with_env_filter("info,scratchy=trace,tower_http=trace")
-        .init();
-    debug!("tracing initialized");
-
-    trace!("trace");
-    debug!("debug");
-    info!("info");
-    warn!("warn");
-    error!("error");
-
-    debug!("setting panic hook");
-    std::panic::set_hook(Box::new(panic_hook));
+    app::init_tracing();
 
-    debug!("initializing app state");
-    let state = AppState::new(secrets, pool);
+    tracing::debug!("initializing app state");
+    let config = state::Config::new(|key| secrets.get(key)).map_err(anyhow::Error::from)?;
+    let state = state::AppState::new(config, pool);
 
-    debug!("running migrations");
-    sqlx::migrate!("./migrations")
-        .run(&state.pool)
-        .await
-        .expect("database migration failed");
+    let router = app::build(state).await?;
 
-    debug!("registering commands");
-    register_commands(state.config.token.to_string())
-        .await
-        .expect("failed to register commands");
-
-    debug!("registering metadata");
-    register_metadata(&state)
-        .await
-        .expect("failed to register metadata");
-
-    debug!("creating router");
-    let router = Router::new()
-        .route("/hello", get(hello_world))
-        .route("/interactions", post(interaction_handler))
-        .merge(linked_roles::router())
-        .layer(TraceLayer::new_for_http())
-        .with_state(state.clone());
-
-    debug!("spawning background metadata updater");
-    spawn_background_updater(state);
-
-    debug!("returning router");
+    tracing::debug!("returning router");
     Ok(router.into())
 }
+
+#[cfg(all(feature = "standalone", not(feature = "shuttle")))]
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    standalone::run().await
+}
@@ -0,0 +1,209 @@
+//! Prometheus metrics, served at `/metrics` in the text format.
+//!
+//! The metrics are global, so that code without access to [`AppState`] can record them too.
+
+use std::{sync::OnceLock, time::Duration};
+
+use axum::{extract::State, routing::get, Router};
+use hyper::StatusCode;
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::state::AppState;
+
+pub struct Metrics {
+    registry: Registry,
+    /// Labels: `kind` (command or component), `name`, `outcome` (ok or error)
+    pub interactions: IntCounterVec,
+    /// Labels: `kind`, `name`
+    pub interaction_duration: HistogramVec,
+    /// Labels: `host`, `endpoint`, `outcome`
+    pub upstream_requests: IntCounterVec,
+    /// Labels: `host`, `endpoint`
+    pub upstream_duration: HistogramVec,
+    /// Labels: `outcome` (ok or error)
+    pub updater_updates: IntCounterVec,
+    /// How long ago the least recently updated metadata was updated
+    pub updater_lag: Gauge,
+    pub signature_failures: IntCounter,
+    /// Labels: `outcome`
+    pub oauth_callbacks: IntCounterVec,
+    pub db_pool_size: IntGauge,
+    pub db_pool_idle: IntGauge,
+    pub db_pool_max: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("scratchy".into()), None)?;
+
+        let interactions = IntCounterVec::new(
+            Opts::new("interactions_total", "Handled interactions"),
+            &["kind", "name", "outcome"],
+        )?;
+        let interaction_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "interaction_duration_seconds",
+                "Time to respond to an interaction",
+            ),
+            &["kind", "name"],
+        )?;
+        let upstream_requests = IntCounterVec::new(
+            Opts::new("upstream_requests_total", "Requests to Scratch APIs"),
+            &["host", "endpoint", "outcome"],
+        )?;
+        let upstream_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "upstream_request_duration_seconds",
+                "Latency of requests to Scratch APIs",
+            ),
+            &["host", "endpoint"],
+        )?;
+        let updater_updates = IntCounterVec::new(
+            Opts::new(
+                "updater_updates_total",
+                "Metadata updates by the background updater",
+            ),
+            &["outcome"],
+        )?;
+        let updater_lag = Gauge::new(
+            "updater_lag_seconds",
+            "Age of the least recently updated metadata",
+        )?;
+        let signature_failures = IntCounter::new(
+            "signature_failures_total",
+            "Interactions rejected because of an invalid signature",
+        )?;
+        let oauth_callbacks = IntCounterVec::new(
+            Opts::new("oauth_callbacks_total", "Linked roles OAuth callbacks"),
+            &["outcome"],
+        )?;
+        let db_pool_size = IntGauge::new("db_pool_connections", "Open database connections")?;
+        let db_pool_idle = IntGauge::new("db_pool_idle_connections", "Idle database connections")?;
+        let db_pool_max = IntGauge::new("db_pool_max_connections", "Database connection limit")?;
+
+        registry.register(Box::new(interactions.clone()))?;
+        registry.register(Box::new(interaction_duration.clone()))?;
+        registry.register(Box::new(upstream_requests.clone()))?;
+        registry.register(Box::new(upstream_duration.clone()))?;
+        registry.register(Box::new(updater_updates.clone()))?;
+        registry.register(Box::new(updater_lag.clone()))?;
+        registry.register(Box::new(signature_failures.clone()))?;
+        registry.register(Box::new(oauth_callbacks.clone()))?;
+        registry.register(Box::new(db_pool_size.clone()))?;
+        registry.register(Box::new(db_pool_idle.clone()))?;
+        registry.register(Box::new(db_pool_max.clone()))?;
+
+        Ok(Self {
+            registry,
+            interactions,
+            interaction_duration,
+            upstream_requests,
+            upstream_duration,
+            updater_updates,
+            updater_lag,
+            signature_failures,
+            oauth_callbacks,
+            db_pool_size,
+            db_pool_idle,
+            db_pool_max,
+        })
+    }
+
+    /// Records a handled command or component.
+    pub fn interaction(&self, kind: &str, name: &str, ok: bool, duration: Duration) {
+        self.interactions
+            .with_label_values(&[kind, name, outcome(ok)])
+            .inc();
+        self.interaction_duration
+            .with_label_values(&[kind, name])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn upstream_request(&self, host: &str, endpoint: &str, outcome: &str, duration: Duration) {
+        self.upstream_requests
+            .with_label_values(&[host, endpoint, outcome])
+            .inc();
+        self.upstream_duration
+            .with_label_values(&[host, endpoint])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn updater_update(&self, ok: bool) {
+        self.updater_updates.with_label_values(&[outcome(ok)]).inc();
+    }
+
+    pub fn oauth_callback(&self, outcome: &str) {
+        self.oauth_callbacks.with_label_values(&[outcome]).inc();
+    }
+
+    fn encode(&self, pool: &PgPool) -> prometheus::Result<String> {
+        // Pool usage is only needed when scraped, so it isn't tracked all the time
+        self.db_pool_size.set(pool.size() as i64);
+        self.db_pool_idle.set(pool.num_idle() as i64);
+        self.db_pool_max
+            .set(pool.options().get_max_connections() as i64);
+
+        let mut buf = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf)?;
+
+        // Safe to unwrap because the text format is always UTF-8
+        Ok(String::from_utf8(buf).unwrap())
+    }
+}
+
+fn outcome(ok: bool) -> &'static str {
+    if ok {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+    METRICS.get_or_init(|| Metrics::new().expect("invalid metric definitions"))
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/metrics", get(handler))
+}
+
+async fn handler(State(pool): State<PgPool>) -> Result<String, StatusCode> {
+    metrics().encode(&pool).map_err(|err| {
+        error!("failed to encode metrics: {err}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions() {
+        Metrics::new().unwrap();
+    }
+
+    #[test]
+    fn text_format() {
+        let metrics = Metrics::new().unwrap();
+        metrics.interaction("command", "ping", true, Duration::from_millis(20));
+        metrics.signature_failures.inc();
+
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&metrics.registry.gather(), &mut buf)
+            .unwrap();
+        let text = String::from_utf8(buf).unwrap();
+
+        assert!(text
+            .contains(r#"scratchy_interactions_total{kind="command",name="ping",outcome="ok"} 1"#));
+        assert!(text.contains("scratchy_signature_failures_total 1"));
+    }
+}
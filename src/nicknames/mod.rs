@@ -0,0 +1,81 @@
+//! Nicknames matching Scratch usernames, for servers which opted in with `/config nicknames`.
+
+use thiserror::Error;
+use tracing::{debug, warn};
+use twilight_http::error::ErrorType;
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    database::{Database, PendingVerification},
+    state::AppState,
+};
+
+#[derive(Debug, Error)]
+pub enum NicknameError {
+    /// The member's highest role is above the bot's, or the member owns the server
+    #[error("missing permissions to change the nickname")]
+    MissingPermissions,
+    #[error(transparent)]
+    TwilightHttp(#[from] twilight_http::Error),
+    #[error(transparent)]
+    Validation(#[from] twilight_validate::request::ValidationError),
+}
+
+pub async fn set_nickname(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    id: Id<UserMarker>,
+    username: &str,
+) -> Result<(), NicknameError> {
+    let result = state
+        .discord_client
+        .update_guild_member(guild_id, id)
+        .nick(Some(username))?
+        .await;
+
+    match result {
+        Ok(_) => {
+            debug!("set nickname of {id} in {guild_id} to {username}");
+            Ok(())
+        }
+        Err(err) if matches!(err.kind(), ErrorType::Response { status, .. } if status.get() == 403) => {
+            Err(NicknameError::MissingPermissions)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Sets the nickname to the primary linked account if the server opted in.
+pub async fn sync_after_link(state: &AppState, verification: &PendingVerification) {
+    let result = async {
+        let Some(config) = state.guild_config(verification.guild_id).await? else {
+            return Ok(());
+        };
+
+        if config.sync_nicknames {
+            // The primary account, which is the new one only if there were no others
+            let linked_accounts = state
+                .pool
+                .get_linked_scratch_accounts(verification.id)
+                .await?;
+
+            if let Some(account) = linked_accounts.first() {
+                set_nickname(state, config.guild_id, verification.id, &account.username).await?;
+            }
+        }
+
+        anyhow::Ok(())
+    }
+    .await;
+
+    // Nobody is waiting for the nickname, so there's no one to report the error to
+    if let Err(err) = result {
+        warn!(
+            "failed to sync nickname of {} in {:?}: {err}",
+            verification.id, verification.guild_id
+        );
+    }
+}
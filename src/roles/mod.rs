@@ -0,0 +1,315 @@
+//! Roles assigned by the bot based on the same data as Linked Roles,
+//! for servers which don't want members to claim them by hand.
+
+use std::collections::{HashMap, HashSet};
+
+use time::{Duration, OffsetDateTime};
+use tokio::time::{interval, Interval, MissedTickBehavior};
+use tracing::{debug, error};
+use twilight_http::error::ErrorType;
+use twilight_model::id::{
+    marker::{GuildMarker, RoleMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    audit::list_members,
+    database::{Database, RoleRule, ScratchAccount},
+    linked_roles::{fetch_role_connection, RoleConnectionData},
+    locales::Locale,
+    state::AppState,
+};
+
+/// What the member's linked accounts must satisfy to get the role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// At least one account has the Scratcher status
+    Scratcher,
+    /// At least this many followers
+    Followers(i64),
+    /// The oldest account was created at least this many days ago
+    JoinedDaysAgo(i64),
+}
+
+impl Condition {
+    pub fn from_parts(kind: i16, value: i64) -> Option<Self> {
+        match kind {
+            0 => Some(Self::Scratcher),
+            1 => Some(Self::Followers(value)),
+            2 => Some(Self::JoinedDaysAgo(value)),
+            _ => None,
+        }
+    }
+
+    /// Kind and value stored in the database.
+    pub fn into_parts(self) -> (i16, i64) {
+        match self {
+            Self::Scratcher => (0, 1),
+            Self::Followers(value) => (1, value),
+            Self::JoinedDaysAgo(value) => (2, value),
+        }
+    }
+
+    pub fn matches(&self, data: &RoleConnectionData, now: OffsetDateTime) -> bool {
+        match *self {
+            Self::Scratcher => data.scratcher,
+            Self::Followers(followers) => data.followers >= followers,
+            Self::JoinedDaysAgo(days) => data.joined <= now - Duration::days(days),
+        }
+    }
+
+    pub fn describe(&self, locale: Locale) -> String {
+        match self {
+            Self::Scratcher => locale.condition_scratcher(),
+            Self::Followers(followers) => locale.condition_followers(&followers.to_string()),
+            Self::JoinedDaysAgo(days) => locale.condition_joined(&days.to_string()),
+        }
+    }
+}
+
+/// Recalculates the data from the user's linked accounts and updates their roles.
+///
+/// Used right after linking, so that the user doesn't wait for [`sync_all`].
+pub async fn sync(state: &AppState, id: Id<UserMarker>) -> anyhow::Result<()> {
+    let rules = state.pool.get_role_rules().await?;
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let linked_accounts = state.pool.get_linked_scratch_accounts(id).await?;
+    let data = fetch_role_connection(linked_accounts, &state.reqwest_client)
+        .await?
+        .map(|role_connection| role_connection.metadata);
+
+    apply(state, id, rules, data.as_ref()).await;
+
+    Ok(())
+}
+
+/// Updates the roles of every linked member in the servers with rules, once a day.
+///
+/// Runs from the linked accounts, so it doesn't depend on Linked Roles. Members are listed
+/// per server, so that only servers the user is in are updated, which requires the Server Members
+/// privileged intent.
+pub async fn sync_all(state: &AppState) -> anyhow::Result<()> {
+    let rules = state.pool.get_role_rules().await?;
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let now = OffsetDateTime::now_utc();
+
+    // Ensure at least 10 seconds for every batch of ScratchDB calls, like the metadata update
+    let mut delay = interval(std::time::Duration::from_secs(10));
+    delay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // Users are often in more than one of the servers
+    let mut cache: HashMap<Id<UserMarker>, Option<RoleConnectionData>> = HashMap::new();
+
+    for (guild_id, rules) in by_guild(rules) {
+        let members = match list_members(state, guild_id).await {
+            Ok(members) => members,
+            Err(err) => {
+                error!("failed to list members of {guild_id} to update roles: {err}");
+                continue;
+            }
+        };
+
+        let ids: Vec<_> = members.iter().map(|member| member.user.id).collect();
+
+        let mut linked_accounts: HashMap<_, Vec<_>> = HashMap::new();
+        for account in state.pool.get_linked_scratch_accounts_of(&ids).await? {
+            linked_accounts.entry(account.id).or_default().push(account);
+        }
+
+        for member in members {
+            let id = member.user.id;
+            let Some(accounts) = linked_accounts.remove(&id) else {
+                continue;
+            };
+
+            let data = match cache.get(&id) {
+                Some(data) => data.to_owned(),
+                None => {
+                    let data = match find_data(state, id, accounts, &mut delay).await {
+                        Ok(data) => data,
+                        Err(err) => {
+                            error!("failed to get the data of {id} to update roles: {err}");
+                            continue;
+                        }
+                    };
+                    cache.insert(id, data.to_owned());
+                    data
+                }
+            };
+
+            let current: HashSet<_> = member.roles.into_iter().collect();
+            if let Err(err) =
+                apply_member(state, guild_id, id, &current, &rules, data.as_ref(), now).await
+            {
+                error!("failed to update roles of {id} in {guild_id}: {err}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Cached Linked Roles metadata, which the daily update refreshed just before,
+/// or the data from ScratchDB for users who didn't authorize Linked Roles.
+async fn find_data(
+    state: &AppState,
+    id: Id<UserMarker>,
+    accounts: Vec<ScratchAccount>,
+    delay: &mut Interval,
+) -> anyhow::Result<Option<RoleConnectionData>> {
+    if let Some(data) = state.pool.get_metadata(id).await? {
+        return Ok(Some(data));
+    }
+
+    delay.tick().await;
+
+    Ok(fetch_role_connection(accounts, &state.reqwest_client)
+        .await?
+        .map(|role_connection| role_connection.metadata))
+}
+
+fn by_guild(rules: Vec<RoleRule>) -> HashMap<Id<GuildMarker>, Vec<RoleRule>> {
+    let mut by_guild: HashMap<_, Vec<_>> = HashMap::new();
+    for rule in rules {
+        by_guild.entry(rule.guild_id).or_default().push(rule);
+    }
+    by_guild
+}
+
+/// Users without linked accounts lose all roles managed by rules.
+async fn apply(
+    state: &AppState,
+    id: Id<UserMarker>,
+    rules: Vec<RoleRule>,
+    data: Option<&RoleConnectionData>,
+) {
+    let now = OffsetDateTime::now_utc();
+
+    // One failing server shouldn't stop the others from being updated
+    for (guild_id, rules) in by_guild(rules) {
+        if let Err(err) = apply_guild(state, guild_id, id, &rules, data, now).await {
+            error!("failed to update roles of {id} in {guild_id}: {err}");
+        }
+    }
+}
+
+async fn apply_guild(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    id: Id<UserMarker>,
+    rules: &[RoleRule],
+    data: Option<&RoleConnectionData>,
+    now: OffsetDateTime,
+) -> anyhow::Result<()> {
+    let member = match state.discord_client.guild_member(guild_id, id).await {
+        Ok(response) => response.model().await?,
+        Err(err) if is_not_found(&err) => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let current: HashSet<_> = member.roles.into_iter().collect();
+
+    apply_member(state, guild_id, id, &current, rules, data, now).await
+}
+
+async fn apply_member(
+    state: &AppState,
+    guild_id: Id<GuildMarker>,
+    id: Id<UserMarker>,
+    current: &HashSet<Id<RoleMarker>>,
+    rules: &[RoleRule],
+    data: Option<&RoleConnectionData>,
+    now: OffsetDateTime,
+) -> anyhow::Result<()> {
+    for rule in rules {
+        let wanted = data.is_some_and(|data| rule.condition.matches(data, now));
+
+        if wanted && !current.contains(&rule.role_id) {
+            debug!("adding role {} to {id} in {guild_id}", rule.role_id);
+            state
+                .discord_client
+                .add_guild_member_role(guild_id, id, rule.role_id)
+                .await?;
+        } else if !wanted && current.contains(&rule.role_id) {
+            debug!("removing role {} from {id} in {guild_id}", rule.role_id);
+            state
+                .discord_client
+                .remove_guild_member_role(guild_id, id, rule.role_id)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The user isn't a member of the server.
+fn is_not_found(err: &twilight_http::Error) -> bool {
+    matches!(err.kind(), ErrorType::Response { status, .. } if status.get() == 404)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn data() -> RoleConnectionData {
+        RoleConnectionData {
+            scratcher: true,
+            followers: 100,
+            joined: datetime!(2020-01-01 00:00:00 UTC),
+        }
+    }
+
+    #[test]
+    fn scratcher() {
+        let now = datetime!(2023-01-01 00:00:00 UTC);
+
+        assert!(Condition::Scratcher.matches(&data(), now));
+
+        let new_scratcher = RoleConnectionData {
+            scratcher: false,
+            ..data()
+        };
+        assert!(!Condition::Scratcher.matches(&new_scratcher, now));
+    }
+
+    #[test]
+    fn followers() {
+        let now = datetime!(2023-01-01 00:00:00 UTC);
+
+        assert!(Condition::Followers(100).matches(&data(), now), "equal");
+        assert!(!Condition::Followers(101).matches(&data(), now));
+    }
+
+    #[test]
+    fn joined() {
+        let now = datetime!(2021-01-01 00:00:00 UTC);
+
+        assert!(
+            Condition::JoinedDaysAgo(366).matches(&data(), now),
+            "leap year"
+        );
+        assert!(!Condition::JoinedDaysAgo(367).matches(&data(), now));
+    }
+
+    #[test]
+    fn parts() {
+        for condition in [
+            Condition::Scratcher,
+            Condition::Followers(100),
+            Condition::JoinedDaysAgo(365),
+        ] {
+            let (kind, value) = condition.into_parts();
+            assert_eq!(Condition::from_parts(kind, value), Some(condition));
+        }
+
+        assert_eq!(Condition::from_parts(3, 0), None);
+    }
+}
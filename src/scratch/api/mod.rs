@@ -6,7 +6,7 @@ use async_trait::async_trait;
 use reqwest::Client;
 
 pub use project::Project;
-pub use studio::Comment;
+pub use studio::{Comment, Studio};
 pub use user::User;
 
 use super::{GetUrl, ScratchAPIError};
@@ -20,6 +20,8 @@ pub trait ScratchAPIClient {
         project_id: i64,
     ) -> Result<Option<Project>, Self::Error>;
 
+    async fn get_scratch_api_studio(&self, studio_id: i64) -> Result<Option<Studio>, Self::Error>;
+
     async fn get_scratch_api_studio_comments(
         &self,
         studio_id: i64,
@@ -36,22 +38,37 @@ impl ScratchAPIClient for Client {
         &self,
         project_id: i64,
     ) -> Result<Option<Project>, Self::Error> {
-        self.get_url_optional(format!("https://api.scratch.mit.edu/projects/{project_id}"))
-            .await
+        self.get_url_optional(
+            "/projects/{id}",
+            format!("https://api.scratch.mit.edu/projects/{project_id}"),
+        )
+        .await
+    }
+
+    async fn get_scratch_api_studio(&self, studio_id: i64) -> Result<Option<Studio>, Self::Error> {
+        self.get_url_optional(
+            "/studios/{id}",
+            format!("https://api.scratch.mit.edu/studios/{studio_id}"),
+        )
+        .await
     }
 
     async fn get_scratch_api_studio_comments(
         &self,
         studio_id: i64,
     ) -> Result<Option<Vec<Comment>>, Self::Error> {
-        self.get_url_optional(format!(
-            "https://api.scratch.mit.edu/studios/{studio_id}/comments"
-        ))
+        self.get_url_optional(
+            "/studios/{id}/comments",
+            format!("https://api.scratch.mit.edu/studios/{studio_id}/comments"),
+        )
         .await
     }
 
     async fn get_scratch_api_user(&self, username: &str) -> Result<Option<User>, Self::Error> {
-        self.get_url_optional(format!("https://api.scratch.mit.edu/users/{username}"))
-            .await
+        self.get_url_optional(
+            "/users/{username}",
+            format!("https://api.scratch.mit.edu/users/{username}"),
+        )
+        .await
     }
 }
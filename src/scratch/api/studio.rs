@@ -1,6 +1,16 @@
 use serde::Deserialize;
 use time::OffsetDateTime;
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Studio {
+    pub id: i64,
+    pub title: String,
+    pub host: i64,
+    pub public: bool,
+    pub open_to_all: bool,
+    pub comments_allowed: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub struct Comment {
     pub id: i64,
@@ -30,6 +40,44 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn studio() {
+        let str = r#"{
+            "id": 29137750,
+            "title": "Scratchy",
+            "host": 42178181,
+            "description": "",
+            "visibility": "visibile",
+            "public": true,
+            "open_to_all": false,
+            "comments_allowed": true,
+            "image": "https://cdn2.scratch.mit.edu/get_image/gallery/29137750_170x100.png",
+            "history": {
+                "created": "2021-02-21T10:18:25.000Z",
+                "modified": "2023-06-08T16:00:59.000Z"
+            },
+            "stats": {
+                "comments": 93,
+                "followers": 4,
+                "managers": 1,
+                "projects": 0
+            }
+        }"#;
+
+        let expected = Studio {
+            id: 29137750,
+            title: "Scratchy".into(),
+            host: 42178181,
+            public: true,
+            open_to_all: false,
+            comments_allowed: true,
+        };
+
+        let actual: Studio = serde_json::from_str(str).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn basic() {
         let str = r#"[
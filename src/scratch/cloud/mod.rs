@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{de, Deserialize, Deserializer};
+use time::OffsetDateTime;
+
+use super::{GetUrl, ScratchAPIError};
+
+/// Name of the cloud variable users set to verify their account, including the cloud prefix.
+pub const CLOUD_VARIABLE: &str = "☁ code";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CloudLog {
+    pub user: String,
+    pub verb: String,
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_value")]
+    pub value: String,
+    #[serde(deserialize_with = "deserialize_timestamp_millis")]
+    pub timestamp: OffsetDateTime,
+}
+
+/// Cloud values are usually strings, but numbers set by some clients are stored as JSON numbers.
+fn deserialize_value<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        String(String),
+        Number(serde_json::Number),
+    }
+
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(value) => value,
+        Value::Number(value) => value.to_string(),
+    })
+}
+
+fn deserialize_timestamp_millis<'de, D>(deserializer: D) -> Result<OffsetDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let millis = i64::deserialize(deserializer)?;
+    OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000).map_err(de::Error::custom)
+}
+
+#[async_trait]
+pub trait ScratchCloudClient {
+    type Error;
+
+    async fn get_scratch_cloud_logs(&self, project_id: i64) -> Result<Vec<CloudLog>, Self::Error>;
+}
+
+#[async_trait]
+impl ScratchCloudClient for Client {
+    type Error = ScratchAPIError;
+
+    async fn get_scratch_cloud_logs(&self, project_id: i64) -> Result<Vec<CloudLog>, Self::Error> {
+        self.get_url(
+            "/logs",
+            format!(
+                "https://clouddata.scratch.mit.edu/logs?projectid={project_id}&limit=100&offset=0"
+            ),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn basic() {
+        let str = r#"[
+            {
+                "user": "PMJ_Studio",
+                "verb": "set_var",
+                "name": "☁ code",
+                "value": "4821736590",
+                "timestamp": 1690372800123
+            },
+            {
+                "user": "Dragonoidowy",
+                "verb": "set_var",
+                "name": "☁ code",
+                "value": 1234567890,
+                "timestamp": 1690372700000
+            }
+        ]"#;
+
+        let expected = vec![
+            CloudLog {
+                user: "PMJ_Studio".into(),
+                verb: "set_var".into(),
+                name: "☁ code".into(),
+                value: "4821736590".into(),
+                timestamp: datetime!(2023-07-26 12:00:00.123 UTC),
+            },
+            CloudLog {
+                user: "Dragonoidowy".into(),
+                verb: "set_var".into(),
+                name: "☁ code".into(),
+                value: "1234567890".into(),
+                timestamp: datetime!(2023-07-26 11:58:20.000 UTC),
+            },
+        ];
+
+        let actual: Vec<CloudLog> = serde_json::from_str(str).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -23,14 +23,18 @@ impl ScratchDBClient for Client {
     type Error = ScratchAPIError;
 
     async fn get_scratch_db_user(&self, username: &str) -> Result<Option<User>, Self::Error> {
-        self.get_url_optional(format!(
-            "https://scratchdb.lefty.one/v3/user/info/{username}"
-        ))
+        self.get_url_optional(
+            "/v3/user/info/{username}",
+            format!("https://scratchdb.lefty.one/v3/user/info/{username}"),
+        )
         .await
     }
 
     async fn get_scratch_db_project(&self, id: i64) -> Result<Option<Project>, Self::Error> {
-        self.get_url_optional(format!("https://scratchdb.lefty.one/v3/project/info/{id}"))
-            .await
+        self.get_url_optional(
+            "/v3/project/info/{id}",
+            format!("https://scratchdb.lefty.one/v3/project/info/{id}"),
+        )
+        .await
     }
 }
@@ -47,6 +47,31 @@ impl<'de> Deserialize<'de> for Status {
     }
 }
 
+impl From<Status> for i16 {
+    fn from(value: Status) -> Self {
+        match value {
+            Status::Scratcher => 0,
+            Status::NewScratcher => 1,
+            Status::TeacherAccount => 2,
+            Status::ScratchTeam => 3,
+        }
+    }
+}
+
+impl TryFrom<i16> for Status {
+    type Error = i16;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Scratcher),
+            1 => Ok(Self::NewScratcher),
+            2 => Ok(Self::TeacherAccount),
+            3 => Ok(Self::ScratchTeam),
+            other => Err(other),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub struct Statistics {
     pub ranks: Ranks,
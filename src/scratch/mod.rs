@@ -1,26 +1,35 @@
+use std::time::Instant;
+
 use async_trait::async_trait;
-use reqwest::{Client, Error, IntoUrl, StatusCode};
+use reqwest::{Client, Error, IntoUrl, StatusCode, Url};
 use serde::Deserialize;
 use thiserror::Error;
 
+use crate::metrics::metrics;
+
 pub mod api;
+pub mod cloud;
 pub mod db;
 pub mod site;
 
-pub const STUDIO_ID: i64 = 29137750;
-pub const STUDIO_URL: &str = "https://scratch.mit.edu/studios/29137750/comments";
+/// Used when no verification studios are configured.
+pub const DEFAULT_STUDIO_ID: i64 = 29137750;
 
+/// Requests are recorded in the metrics by host and `endpoint`, a template of the URL path
+/// without usernames or IDs, so that the number of labels stays small.
 #[async_trait]
 trait GetUrl {
     type Error;
 
     async fn get_url<T: for<'de> Deserialize<'de>>(
         &self,
+        endpoint: &'static str,
         url: impl IntoUrl + Send,
     ) -> Result<T, Self::Error>;
 
     async fn get_url_optional<T: for<'de> Deserialize<'de>>(
         &self,
+        endpoint: &'static str,
         url: impl IntoUrl + Send,
     ) -> Result<Option<T>, Self::Error>;
 }
@@ -31,29 +40,72 @@ impl GetUrl for Client {
 
     async fn get_url<T: for<'de> Deserialize<'de>>(
         &self,
+        endpoint: &'static str,
         url: impl IntoUrl + Send,
     ) -> Result<T, Self::Error> {
-        Ok(self
-            .get(url)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?)
+        let url = url.into_url()?;
+        let start = Instant::now();
+
+        let result = async {
+            Ok(self
+                .get(url.clone())
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?)
+        }
+        .await;
+
+        record(&url, endpoint, outcome(&result), start);
+        result
     }
 
     async fn get_url_optional<T: for<'de> Deserialize<'de>>(
         &self,
+        endpoint: &'static str,
         url: impl IntoUrl + Send,
     ) -> Result<Option<T>, Self::Error> {
-        Ok(match self.get(url).send().await?.error_for_status() {
-            Ok(res) => Some(res.json().await?),
-            Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => None,
-            Err(err) => Err(err)?,
-        })
+        let url = url.into_url()?;
+        let start = Instant::now();
+
+        let result = async {
+            Ok(
+                match self.get(url.clone()).send().await?.error_for_status() {
+                    Ok(res) => Some(res.json().await?),
+                    Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => None,
+                    Err(err) => Err(err)?,
+                },
+            )
+        }
+        .await;
+
+        let outcome = match result {
+            Ok(None) => "not_found",
+            _ => outcome(&result),
+        };
+        record(&url, endpoint, outcome, start);
+        result
+    }
+}
+
+fn outcome<T>(result: &Result<T, ScratchAPIError>) -> &'static str {
+    match result {
+        Ok(_) => "ok",
+        Err(ScratchAPIError::ServerError) => "server_error",
+        Err(ScratchAPIError::Other(_)) => "error",
     }
 }
 
+fn record(url: &Url, endpoint: &str, outcome: &str, start: Instant) {
+    metrics().upstream_request(
+        url.host_str().unwrap_or_default(),
+        endpoint,
+        outcome,
+        start.elapsed(),
+    );
+}
+
 #[derive(Debug, Error)]
 pub enum ScratchAPIError {
     #[error("Server error")]
@@ -1,5 +1,13 @@
 pub fn project_link(id: i64) -> String {
-    format!("[{id}](https://scratch.mit.edu/projects/{id})")
+    format!("[{id}]({})", project_url(id))
+}
+
+pub fn project_url(id: i64) -> String {
+    format!("https://scratch.mit.edu/projects/{id}")
+}
+
+pub fn studio_comments_url(id: i64) -> String {
+    format!("https://scratch.mit.edu/studios/{id}/comments")
 }
 
 pub fn user_link(username: &str) -> String {
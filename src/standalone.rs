@@ -0,0 +1,192 @@
+//! Self-hosted entry point, without Shuttle.
+//!
+//! Every setting has the same name as its Shuttle secret. It's read from the uppercase
+//! environment variable (`DISCORD_TOKEN` for `discord_token`) or else from the TOML file
+//! at `SCRATCHY_CONFIG`, if set. The database is configured with `database_url` and the
+//! server listens on `bind_address`, `0.0.0.0:8000` by default.
+//!
+//! `--check-config` validates the settings and exits instead of starting the server.
+//! It also checks that the Discord token belongs to `discord_client_id`, unless given
+//! `--no-probe` for checking offline. Both flags only exist here: the Shuttle deployment has no
+//! command line, and reports the same problems when it fails to start.
+
+use std::{collections::HashMap, env, fs, net::SocketAddr, process};
+
+use anyhow::{bail, Context};
+use sqlx::postgres::PgPoolOptions;
+use tracing::{debug, info};
+use twilight_http::Client as TwilightClient;
+
+use crate::{
+    app,
+    state::{AppState, Config, ConfigError, ConfigErrors},
+};
+
+#[derive(Debug, Default)]
+pub struct Settings {
+    file: HashMap<String, String>,
+}
+
+impl Settings {
+    pub fn load() -> anyhow::Result<Self> {
+        let Ok(path) = env::var("SCRATCHY_CONFIG") else {
+            return Ok(Self::default());
+        };
+
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {path}"))?;
+        let table: toml::Table =
+            toml::from_str(&content).with_context(|| format!("failed to parse {path}"))?;
+
+        let file = table
+            .into_iter()
+            .map(|(key, value)| (key, to_setting(value)))
+            .collect();
+
+        Ok(Self { file })
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        env::var(key.to_uppercase())
+            .ok()
+            .or_else(|| self.file.get(key).cloned())
+    }
+}
+
+/// Same format as the secrets, so numbers and lists work without quotes.
+fn to_setting(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value,
+        toml::Value::Array(values) => values
+            .into_iter()
+            .map(to_setting)
+            .collect::<Vec<_>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
+
+/// Prints every problem with the settings. Returns whether there were none.
+async fn check_config(settings: &Settings, probe: bool) -> bool {
+    let result = Config::new(|key| settings.get(key));
+
+    let mut errors = match &result {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.0.clone(),
+    };
+
+    if settings.get("database_url").is_none() {
+        errors.push(ConfigError::Missing("database_url"));
+    }
+
+    if let Some(address) = settings.get("bind_address") {
+        if address.parse::<SocketAddr>().is_err() {
+            errors.push(ConfigError::Invalid(
+                "bind_address",
+                "a valid socket address",
+            ));
+        }
+    }
+
+    let config = match result {
+        Ok(config) if errors.is_empty() => config,
+        _ => {
+            println!("{}", ConfigErrors(errors));
+            return false;
+        }
+    };
+
+    // Only once the rest is valid, since it needs the token and client ID
+    if probe {
+        if let Err(err) = probe_token(&config).await {
+            println!("discord_token failed the probe: {err:#}");
+            return false;
+        }
+    }
+
+    println!("config OK");
+    true
+}
+
+/// Checks that the token is valid and belongs to the configured application.
+async fn probe_token(config: &Config) -> anyhow::Result<()> {
+    let application = TwilightClient::new(config.token.to_owned())
+        .current_user_application()
+        .await?
+        .model()
+        .await?;
+
+    if application.id.to_string() != config.client_id {
+        bail!("it belongs to application {}", application.id);
+    }
+
+    Ok(())
+}
+
+pub async fn run() -> anyhow::Result<()> {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let settings = Settings::load()?;
+
+    if args.iter().any(|arg| arg == "--check-config") {
+        let probe = !args.iter().any(|arg| arg == "--no-probe");
+        if !check_config(&settings, probe).await {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    app::init_tracing();
+
+    // Before connecting, so that config problems show up first
+    let config = Config::new(|key| settings.get(key))?;
+
+    debug!("connecting to the database");
+    let database_url = settings
+        .get("database_url")
+        .context("missing database_url")?;
+    let pool = PgPoolOptions::new().connect(&database_url).await?;
+
+    debug!("initializing app state");
+    let state = AppState::new(config, pool);
+
+    let router = app::build(state).await?;
+
+    let address: SocketAddr = settings
+        .get("bind_address")
+        .unwrap_or("0.0.0.0:8000".into())
+        .parse()
+        .context("invalid bind_address")?;
+
+    info!("listening on {address}");
+    axum::Server::bind(&address)
+        .serve(router.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_values() {
+        let table: toml::Table = toml::from_str(
+            r#"
+                discord_token = "token"
+                cloud_project_id = 123
+                studio_ids = [29137750, 34104948]
+            "#,
+        )
+        .unwrap();
+
+        let values: HashMap<_, _> = table
+            .into_iter()
+            .map(|(key, value)| (key, to_setting(value)))
+            .collect();
+
+        assert_eq!(values["discord_token"], "token");
+        assert_eq!(values["cloud_project_id"], "123");
+        assert_eq!(values["studio_ids"], "29137750,34104948");
+    }
+}
@@ -1,42 +1,85 @@
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
 use axum::extract::FromRef;
 use ed25519_dalek::PublicKey;
 use oauth2::basic::BasicClient;
 use reqwest::{Client, Url};
-use shuttle_secrets::SecretStore;
 use sqlx::PgPool;
-use time::OffsetDateTime;
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use twilight_http::Client as TwilightClient;
+use twilight_model::id::{
+    marker::{ApplicationMarker, GuildMarker},
+    Id,
+};
 
-use crate::{embeds::timestamp, linked_roles::create_oauth_client};
+use crate::{
+    database::{Database, GuildConfig},
+    embeds::timestamp,
+    linked_roles::{create_oauth_client, UpdaterStatus},
+    scratch::DEFAULT_STUDIO_ID,
+    verification::StudioPool,
+};
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub config: Config,
     pub oauth_client: BasicClient,
     pub reqwest_client: Client,
+    pub discord_client: Arc<TwilightClient>,
     pub pool: PgPool,
     pub start_time: StartTime,
+    pub studios: StudioPool,
+    pub updater: UpdaterStatus,
 }
 
 impl AppState {
-    pub fn new(secrets: SecretStore, pool: PgPool) -> Self {
-        let config = Config::new(secrets);
-
+    pub fn new(config: Config, pool: PgPool) -> Self {
         let oauth_client = create_oauth_client(&config);
 
         let reqwest_client = Client::new();
 
+        let discord_client = Arc::new(TwilightClient::new(config.token.to_owned()));
+
         let start_time = StartTime::new();
 
+        let studios = StudioPool::new(&config.studio_ids);
+
+        let updater = UpdaterStatus::default();
+
         Self {
             config,
             oauth_client,
             reqwest_client,
+            discord_client,
             pool,
             start_time,
+            studios,
+            updater,
         }
     }
+
+    /// Settings of the server the interaction came from, `None` in DMs.
+    pub async fn guild_config(
+        &self,
+        guild_id: Option<Id<GuildMarker>>,
+    ) -> Result<Option<GuildConfig>, sqlx::Error> {
+        let Some(guild_id) = guild_id else {
+            return Ok(None);
+        };
+
+        Ok(Some(
+            self.pool
+                .get_guild_config(guild_id)
+                .await?
+                .unwrap_or_else(|| GuildConfig::new(guild_id)),
+        ))
+    }
 }
 
+/// The `/link` message is edited through the interaction token, which expires after 15 minutes.
+const MAX_VERIFICATION_EXPIRY_MINUTES: i64 = 15;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub redirect_url: Url,
@@ -44,41 +87,198 @@ pub struct Config {
     pub client_secret: String,
     pub public_key: PublicKey,
     pub token: String,
+    pub cloud_project_id: Option<i64>,
+    pub verification_expiry: Duration,
+    pub studio_ids: Vec<i64>,
+    /// Bearer token for `/diag`, which is disabled without it
+    pub admin_token: Option<String>,
+    /// Commands are registered only in this guild instead of globally, for testing
+    pub dev_guild_id: Option<Id<GuildMarker>>,
+    /// DM users when their linked accounts are gone from Scratch
+    pub notify_gone_accounts: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("missing {0}")]
+    Missing(&'static str),
+    /// Values aren't included, because most of them are secrets
+    #[error("{0} is not {1}")]
+    Invalid(&'static str, &'static str),
+}
+
+/// Every problem with the config, so they can be fixed all at once.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl Display for ConfigErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "found {} problem(s) with the config:", self.0.len())?;
+        for error in &self.0 {
+            write!(f, "\n- {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads settings and collects the errors instead of stopping at the first one.
+struct Reader<F> {
+    get: F,
+    errors: Vec<ConfigError>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Reader<F> {
+    fn required(&mut self, key: &'static str) -> Option<String> {
+        let value = (self.get)(key);
+        if value.is_none() {
+            self.errors.push(ConfigError::Missing(key));
+        }
+        value
+    }
+
+    fn check<T, E>(
+        &mut self,
+        key: &'static str,
+        expected: &'static str,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.errors.push(ConfigError::Invalid(key, expected));
+                None
+            }
+        }
+    }
+
+    fn optional<T: FromStr>(
+        &mut self,
+        key: &'static str,
+        expected: &'static str,
+    ) -> Option<Option<T>> {
+        match (self.get)(key) {
+            Some(value) => self.check(key, expected, value.trim().parse()).map(Some),
+            None => Some(None),
+        }
+    }
 }
 
 impl Config {
-    pub fn new(secrets: SecretStore) -> Self {
-        let mut redirect_url: Url = secrets
-            .get("base_url")
-            .expect("missing base_url")
-            .parse()
-            .expect("invalid base_url");
-        redirect_url.set_path("discord-oauth-callback");
-
-        let client_id = secrets
-            .get("discord_client_id")
-            .expect("missing discord_client_id");
-
-        let client_secret = secrets
-            .get("discord_client_secret")
-            .expect("missing discord_client_secret");
-
-        let public_key = secrets
-            .get("discord_public_key")
-            .expect("missing discord_public_key");
-        let public_key =
-            &hex::decode(public_key).expect("discord_public_key is not a valid hex string");
-        let public_key = PublicKey::from_bytes(&public_key)
-            .expect("discord_public_key is not a valid public key");
-
-        let token = secrets.get("discord_token").expect("missing discord_token");
+    /// Reads the settings by their Shuttle secret names, whatever the source.
+    pub fn new(get: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigErrors> {
+        let mut reader = Reader {
+            get,
+            errors: Vec::new(),
+        };
 
-        Self {
+        let redirect_url = reader.required("base_url").and_then(|value| {
+            let mut url: Url = reader.check("base_url", "a valid URL", value.parse())?;
+            url.set_path("discord-oauth-callback");
+            Some(url)
+        });
+
+        let client_id = reader.required("discord_client_id").and_then(|value| {
+            reader.check(
+                "discord_client_id",
+                "a valid application ID",
+                value.parse::<Id<ApplicationMarker>>(),
+            )?;
+            Some(value)
+        });
+
+        let client_secret = reader.required("discord_client_secret");
+
+        let public_key = reader
+            .required("discord_public_key")
+            .and_then(|value| {
+                reader.check(
+                    "discord_public_key",
+                    "a valid hex string",
+                    hex::decode(value),
+                )
+            })
+            .and_then(|bytes| {
+                reader.check(
+                    "discord_public_key",
+                    "a valid public key",
+                    PublicKey::from_bytes(&bytes),
+                )
+            });
+
+        let token = reader.required("discord_token");
+
+        let cloud_project_id = reader.optional("cloud_project_id", "a valid project ID");
+
+        let verification_expiry = reader
+            .optional("verification_expiry_minutes", "a valid number")
+            .and_then(|minutes| {
+                let minutes = minutes.unwrap_or(5);
+                reader.check(
+                    "verification_expiry_minutes",
+                    "between 1 and 15",
+                    (1..=MAX_VERIFICATION_EXPIRY_MINUTES)
+                        .contains(&minutes)
+                        .then_some(Duration::minutes(minutes))
+                        .ok_or(()),
+                )
+            });
+
+        let studio_ids = match (reader.get)("studio_ids") {
+            Some(value) => {
+                let ids: Result<Vec<_>, _> = value
+                    .split(',')
+                    .map(|id| id.trim().parse::<i64>())
+                    .collect();
+                reader.check("studio_ids", "a list of studio IDs", ids)
+            }
+            None => Some(vec![DEFAULT_STUDIO_ID]),
+        };
+
+        let admin_token = (reader.get)("admin_token");
+
+        let dev_guild_id = reader.optional("dev_guild_id", "a valid guild ID");
+
+        let notify_gone_accounts = reader
+            .optional("notify_gone_accounts", "true or false")
+            .map(|enabled| enabled.unwrap_or(false));
+
+        match (
             redirect_url,
             client_id,
             client_secret,
             public_key,
             token,
+            cloud_project_id,
+            verification_expiry,
+            studio_ids,
+            dev_guild_id,
+            notify_gone_accounts,
+        ) {
+            (
+                Some(redirect_url),
+                Some(client_id),
+                Some(client_secret),
+                Some(public_key),
+                Some(token),
+                Some(cloud_project_id),
+                Some(verification_expiry),
+                Some(studio_ids),
+                Some(dev_guild_id),
+                Some(notify_gone_accounts),
+            ) if reader.errors.is_empty() => Ok(Self {
+                redirect_url,
+                client_id,
+                client_secret,
+                public_key,
+                token,
+                cloud_project_id,
+                verification_expiry,
+                studio_ids,
+                admin_token,
+                dev_guild_id,
+                notify_gone_accounts,
+            }),
+            _ => Err(ConfigErrors(reader.errors)),
         }
     }
 }
@@ -125,3 +325,112 @@ impl FromRef<AppState> for StartTime {
         input.start_time.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn settings() -> HashMap<&'static str, &'static str> {
+        HashMap::from([
+            ("base_url", "https://example.com"),
+            ("discord_client_id", "1234"),
+            ("discord_client_secret", "secret"),
+            (
+                "discord_public_key",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            ),
+            ("discord_token", "token"),
+        ])
+    }
+
+    fn load(settings: HashMap<&'static str, &'static str>) -> Result<Config, ConfigErrors> {
+        Config::new(|key| settings.get(key).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn valid() {
+        let config = load(settings()).unwrap();
+
+        assert_eq!(
+            config.redirect_url.as_str(),
+            "https://example.com/discord-oauth-callback"
+        );
+        assert_eq!(config.cloud_project_id, None);
+        assert_eq!(config.verification_expiry, Duration::minutes(5));
+        assert_eq!(config.studio_ids, vec![DEFAULT_STUDIO_ID]);
+        assert!(!config.notify_gone_accounts);
+    }
+
+    #[test]
+    fn optional() {
+        let mut settings = settings();
+        settings.insert("cloud_project_id", "123");
+        settings.insert("verification_expiry_minutes", "10");
+        settings.insert("studio_ids", "1, 2");
+        settings.insert("notify_gone_accounts", "true");
+
+        let config = load(settings).unwrap();
+
+        assert_eq!(config.cloud_project_id, Some(123));
+        assert_eq!(config.verification_expiry, Duration::minutes(10));
+        assert_eq!(config.studio_ids, vec![1, 2]);
+        assert!(config.notify_gone_accounts);
+    }
+
+    #[test]
+    fn all_errors() {
+        let mut settings = settings();
+        settings.remove("discord_token");
+        settings.remove("discord_client_secret");
+        settings.insert("base_url", "not a url");
+        settings.insert("discord_public_key", "not hex");
+        settings.insert("studio_ids", "1,a");
+
+        let errors = load(settings).unwrap_err();
+
+        assert_eq!(
+            errors,
+            ConfigErrors(vec![
+                ConfigError::Invalid("base_url", "a valid URL"),
+                ConfigError::Missing("discord_client_secret"),
+                ConfigError::Invalid("discord_public_key", "a valid hex string"),
+                ConfigError::Missing("discord_token"),
+                ConfigError::Invalid("studio_ids", "a list of studio IDs"),
+            ])
+        );
+    }
+
+    #[test]
+    fn verification_expiry_too_long() {
+        let mut settings = settings();
+        settings.insert("verification_expiry_minutes", "30");
+
+        let errors = load(settings).unwrap_err();
+
+        assert_eq!(
+            errors,
+            ConfigErrors(vec![ConfigError::Invalid(
+                "verification_expiry_minutes",
+                "between 1 and 15"
+            )])
+        );
+    }
+
+    #[test]
+    fn invalid_public_key() {
+        let mut settings = settings();
+        settings.insert("discord_public_key", "abcd");
+
+        let errors = load(settings).unwrap_err();
+
+        assert_eq!(
+            errors,
+            ConfigErrors(vec![ConfigError::Invalid(
+                "discord_public_key",
+                "a valid public key"
+            )])
+        );
+    }
+}
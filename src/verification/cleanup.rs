@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use tokio::{
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, error};
+
+use crate::{database::Database, state::AppState};
+
+pub fn spawn(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(cleanup(state))
+}
+
+async fn cleanup(state: AppState) -> () {
+    debug!("starting pending verification cleanup");
+
+    let mut minute = interval(Duration::from_secs(60));
+    minute.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        minute.tick().await;
+
+        match state.pool.delete_expired_pending_verifications().await {
+            Ok(0) => {}
+            Ok(deleted) => debug!("deleted {deleted} expired pending verifications"),
+            Err(err) => error!("{}", err),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use rand::{seq::SliceRandom, Rng};
+
+/// Substrings rejected by Scratch's comment filter, see `blocked.txt`.
+static BLOCKED: &str = include_str!("blocked.txt");
+
+/// Consonants and digits only, so random codes can't form words which the comment filter
+/// would block. Characters easy to confuse with each other (`0`, `1`, `L`) are left out.
+const ALPHABET: &[u8] = b"BCDFGHJKMNPQRSTVWXZ23456789";
+const GROUP_LENGTH: usize = 4;
+const GROUP_COUNT: usize = 3;
+
+const CLOUD_CODE_LENGTH: usize = 10;
+
+/// Generates a code for a studio comment, e.g. `K7QD 3XMB 9TZH`.
+pub fn comment_code() -> String {
+    let mut rng = rand::thread_rng();
+
+    loop {
+        let code = (0..GROUP_COUNT)
+            .map(|_| {
+                (0..GROUP_LENGTH)
+                    .map(|_| *ALPHABET.choose(&mut rng).unwrap() as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if !is_blocked(&code) {
+            return code;
+        }
+    }
+}
+
+/// Generates a code for a cloud variable.
+///
+/// Cloud variables can only hold numbers, and a leading zero could get lost
+/// if the project treats the value as a number, so the first digit is never zero.
+///
+/// Cloud variables aren't filtered, so there's no need to check the code.
+pub fn cloud_code() -> String {
+    let mut rng = rand::thread_rng();
+
+    (0..CLOUD_CODE_LENGTH)
+        .map(|i| {
+            if i == 0 {
+                rng.gen_range('1'..='9')
+            } else {
+                rng.gen_range('0'..='9')
+            }
+        })
+        .collect()
+}
+
+/// Removes whitespace and converts to lowercase, so that codes typed
+/// with different spacing or case are still considered equal.
+pub fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|ch| !ch.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn blocked_substrings() -> impl Iterator<Item = &'static str> {
+    BLOCKED
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+pub fn is_blocked(code: &str) -> bool {
+    let code = normalize(code);
+    blocked_substrings().any(|blocked| code.contains(blocked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocked_list_normalized() {
+        for blocked in blocked_substrings() {
+            assert_eq!(blocked, normalize(blocked), "{blocked:?} is not normalized");
+        }
+    }
+
+    #[test]
+    fn blocked_list_reachable() {
+        let alphabet = normalize(std::str::from_utf8(ALPHABET).unwrap());
+
+        for blocked in blocked_substrings() {
+            assert!(
+                blocked.chars().all(|ch| alphabet.contains(ch)),
+                "{blocked:?} can never be in a code"
+            );
+        }
+    }
+
+    #[test]
+    fn blocked_case_and_whitespace() {
+        assert!(is_blocked("WTF"));
+        assert!(is_blocked("BCDW TFGH"));
+        assert!(is_blocked("K7Q6 9XMB 3TZH"), "across groups");
+        assert!(!is_blocked("BCDF GHJK MNPQ"));
+    }
+
+    #[test]
+    fn comment_code_format() {
+        for _ in 0..1000 {
+            let code = comment_code();
+
+            assert_eq!(code.len(), GROUP_COUNT * (GROUP_LENGTH + 1) - 1, "{code}");
+            assert!(
+                code.split(' ').all(|group| group.len() == GROUP_LENGTH),
+                "{code}"
+            );
+            assert!(
+                code.bytes().all(|ch| ch == b' ' || ALPHABET.contains(&ch)),
+                "{code}"
+            );
+            assert!(!is_blocked(&code), "{code}");
+        }
+    }
+
+    #[test]
+    fn comment_code_no_vowels() {
+        for _ in 0..1000 {
+            let code = normalize(&comment_code());
+
+            assert!(!code.contains(['a', 'e', 'i', 'o', 'u', 'y']), "{code}");
+        }
+    }
+
+    #[test]
+    fn cloud_code_digits_only() {
+        for _ in 0..1000 {
+            let code = cloud_code();
+
+            assert_eq!(code.len(), CLOUD_CODE_LENGTH);
+            assert!(code.chars().all(|ch| ch.is_ascii_digit()), "{code}");
+            assert!(!code.starts_with('0'), "{code}");
+        }
+    }
+
+    #[test]
+    fn normalize_whitespace_and_case() {
+        assert_eq!(normalize(" K7QD 3xmb\n9TZH "), "k7qd3xmb9tzh");
+    }
+}
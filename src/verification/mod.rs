@@ -0,0 +1,266 @@
+mod cleanup;
+pub mod code;
+mod poller;
+mod studios;
+pub mod validate;
+
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use tracing::error;
+use twilight_mention::Mention;
+use twilight_model::id::{marker::UserMarker, Id};
+
+use crate::{
+    database::{
+        complete_verification, transfer_linked_accounts, Database, LinkError, PendingVerification,
+        TransferError,
+    },
+    event_log::{self, Event},
+    linked_roles::RoleConnectionUpdater,
+    locales::Locale,
+    nicknames, roles,
+    scratch::{api::ScratchAPIClient, site::user_link},
+    state::AppState,
+};
+
+pub use cleanup::spawn as spawn_cleanup;
+pub use poller::spawn as spawn_poller;
+pub use studios::{spawn as spawn_studio_health_check, StudioPool};
+
+/// Where the user has to post the code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum Method {
+    /// Comment in the verification studio, the only method before cloud variables
+    #[default]
+    Comment = 0,
+    /// Cloud variable in the verification project
+    Cloud = 1,
+}
+
+impl Method {
+    pub fn generate_code(&self) -> String {
+        match self {
+            Self::Comment => code::comment_code(),
+            Self::Cloud => code::cloud_code(),
+        }
+    }
+}
+
+impl From<Method> for i16 {
+    fn from(value: Method) -> Self {
+        value as i16
+    }
+}
+
+impl TryFrom<i16> for Method {
+    type Error = i16;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Comment),
+            1 => Ok(Self::Cloud),
+            other => Err(other),
+        }
+    }
+}
+
+/// How the link of a Scratch account was verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifiedVia {
+    /// Comment in a verification studio
+    Comment,
+    /// Cloud variable in the verification project
+    Cloud,
+    /// Linked by an administrator without verification
+    Admin,
+    /// Moved from another Discord account
+    Transfer,
+    /// Imported from another bot
+    Import,
+}
+
+impl VerifiedVia {
+    pub fn describe(&self, locale: Locale) -> String {
+        match self {
+            Self::Comment => locale.verified_via_comment(),
+            Self::Cloud => locale.verified_via_cloud(),
+            Self::Admin => locale.verified_via_admin(),
+            Self::Transfer => locale.verified_via_transfer(),
+            Self::Import => locale.verified_via_import(),
+        }
+    }
+}
+
+impl From<Method> for VerifiedVia {
+    fn from(value: Method) -> Self {
+        match value {
+            Method::Comment => Self::Comment,
+            Method::Cloud => Self::Cloud,
+        }
+    }
+}
+
+impl From<VerifiedVia> for i16 {
+    fn from(value: VerifiedVia) -> Self {
+        match value {
+            VerifiedVia::Comment => 0,
+            VerifiedVia::Cloud => 1,
+            VerifiedVia::Admin => 2,
+            VerifiedVia::Transfer => 3,
+            VerifiedVia::Import => 4,
+        }
+    }
+}
+
+impl TryFrom<i16> for VerifiedVia {
+    type Error = i16;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Comment),
+            1 => Ok(Self::Cloud),
+            2 => Ok(Self::Admin),
+            3 => Ok(Self::Transfer),
+            4 => Ok(Self::Import),
+            other => Err(other),
+        }
+    }
+}
+
+/// Links the verified account and removes the pending verification.
+///
+/// Shared by the "Verify" button and the background poller, `None` if the other one
+/// already completed it.
+pub async fn complete(
+    state: &AppState,
+    verification: &PendingVerification,
+) -> Result<Option<Result<(), LinkError>>, sqlx::Error> {
+    // Lets later lookups tell apart a deleted account from a new one with the same name,
+    // accounts linked while Scratch is down get it from the backfill instead
+    let scratch_id = match state
+        .reqwest_client
+        .get_scratch_api_user(&verification.username)
+        .await
+    {
+        Ok(user) => user.map(|user| user.id),
+        Err(err) => {
+            error!("{}", err);
+            None
+        }
+    };
+
+    let (account, stale) =
+        match complete_verification(&state.pool, verification, scratch_id).await? {
+            Some(Ok(linked)) => linked,
+            // The code proves that the owner of the other Discord account is the same person
+            Some(Err(LinkError::AlreadyLinkedToOther(_))) => {
+                return transfer(state, verification).await
+            }
+            Some(Err(err)) => return Ok(Some(Err(err))),
+            None => return Ok(None),
+        };
+
+    if state.pool.get_token(verification.id).await?.is_some() {
+        if let Err(err) = state.update_role_connection(verification.id).await {
+            error!("{}", err);
+        }
+    }
+
+    // The username now belongs to a new account, so its old owner lost the link
+    if let Some(stale) = stale {
+        event_log::log(
+            state,
+            verification.guild_id,
+            Event::Unlinked {
+                id: stale.id,
+                username: stale.username,
+            },
+        );
+    }
+
+    event_log::log(
+        state,
+        verification.guild_id,
+        Event::Linked {
+            id: verification.id,
+            username: verification.username.to_owned(),
+            // Always set when linking
+            linked_at: account.linked_at.unwrap(),
+            via: verification.method.into(),
+        },
+    );
+
+    // Updating the member in every server shouldn't delay the response
+    let state = state.clone();
+    let verification = verification.clone();
+    tokio::spawn(async move {
+        nicknames::sync_after_link(&state, &verification).await;
+
+        if let Err(err) = roles::sync(&state, verification.id).await {
+            error!("{}", err);
+        }
+    });
+
+    Ok(Some(Ok(())))
+}
+
+/// Moves all accounts of the Discord user the verified account is linked to.
+async fn transfer(
+    state: &AppState,
+    verification: &PendingVerification,
+) -> Result<Option<Result<(), LinkError>>, sqlx::Error> {
+    let (from, usernames) = match transfer_linked_accounts(
+        &state.pool,
+        verification.username.to_owned(),
+        verification.id,
+    )
+    .await?
+    {
+        Ok(transferred) => transferred,
+        Err(TransferError::AlreadyLinkedToYou) => {
+            return Ok(Some(Err(LinkError::AlreadyLinkedToYou)))
+        }
+        // Unlinked in the meantime, the verification is used up anyway
+        Err(TransferError::NotLinked) => return Ok(None),
+    };
+
+    for id in [from, verification.id] {
+        if state.pool.get_token(id).await?.is_some() {
+            if let Err(err) = state.update_role_connection(id).await {
+                error!("{}", err);
+            }
+        }
+    }
+
+    event_log::log(
+        state,
+        verification.guild_id,
+        Event::Transferred {
+            from,
+            to: verification.id,
+            usernames,
+        },
+    );
+
+    let state = state.clone();
+    let verification = verification.clone();
+    tokio::spawn(async move {
+        nicknames::sync_after_link(&state, &verification).await;
+
+        for id in [from, verification.id] {
+            if let Err(err) = roles::sync(&state, id).await {
+                error!("{}", err);
+            }
+        }
+    });
+
+    Ok(Some(Ok(())))
+}
+
+pub fn success_message(locale: Locale, id: Id<UserMarker>, username: &str) -> String {
+    format!(
+        "{}\n\n{}",
+        locale.successfully_linked(&id.mention().to_string(), &user_link(username)),
+        locale.linked_roles_message(),
+    )
+}
@@ -0,0 +1,136 @@
+use std::{collections::HashMap, time::Duration};
+
+use tokio::{
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, error, warn};
+
+use crate::{
+    database::{Database, PendingVerification},
+    interactions::FlowMessage,
+    locales::Locale,
+    scratch::{api::ScratchAPIClient, cloud::ScratchCloudClient, DEFAULT_STUDIO_ID},
+    state::AppState,
+};
+
+use super::{
+    complete, success_message,
+    validate::{validate_cloud, validate_comment},
+    Method,
+};
+
+pub fn spawn(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(poller(state))
+}
+
+/// Completes verifications without waiting for the user to press the "Verify" button.
+async fn poller(state: AppState) -> () {
+    debug!("starting verification poller");
+
+    let mut delay = interval(Duration::from_secs(5));
+    // Don't poll the Scratch API more often if a request took longer than the interval
+    delay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        delay.tick().await;
+
+        if let Err(err) = poll(&state).await {
+            error!("{}", err);
+        }
+    }
+}
+
+async fn poll(state: &AppState) -> anyhow::Result<()> {
+    let (comment, cloud): (Vec<_>, Vec<_>) = state
+        .pool
+        .get_active_pending_verifications()
+        .await?
+        .into_iter()
+        .partition(|verification| verification.method == Method::Comment);
+
+    let mut by_studio: HashMap<_, Vec<_>> = HashMap::new();
+    for verification in comment {
+        by_studio
+            .entry(verification.studio_id.unwrap_or(DEFAULT_STUDIO_ID))
+            .or_default()
+            .push(verification);
+    }
+
+    // Only fetch comments and logs when someone is waiting for them
+    for (studio_id, verifications) in by_studio {
+        // One broken studio shouldn't hold up the others, new verifications avoid it until
+        // the next health check
+        let comments = match state
+            .reqwest_client
+            .get_scratch_api_studio_comments(studio_id)
+            .await
+        {
+            Ok(Some(comments)) => comments,
+            Ok(None) => {
+                warn!("verification studio {studio_id} doesn't exist");
+                state.studios.mark_unhealthy(studio_id);
+                continue;
+            }
+            Err(err) => {
+                error!("failed to get comments of verification studio {studio_id}: {err}");
+                state.studios.mark_unhealthy(studio_id);
+                continue;
+            }
+        };
+
+        for verification in verifications {
+            if validate_comment(comments.to_owned(), &verification).is_ok() {
+                finish(state, verification).await;
+            }
+        }
+    }
+
+    if let (false, Some(project_id)) = (cloud.is_empty(), state.config.cloud_project_id) {
+        let logs = state
+            .reqwest_client
+            .get_scratch_cloud_logs(project_id)
+            .await?;
+
+        for verification in cloud {
+            if validate_cloud(logs.to_owned(), &verification).is_ok() {
+                finish(state, verification).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn finish(state: &AppState, verification: PendingVerification) {
+    debug!(
+        "verified {} for {} in the background",
+        verification.username, verification.id
+    );
+
+    match complete(state, &verification).await {
+        Ok(Some(Ok(()))) => {
+            if let Err(err) = edit_message(state, &verification).await {
+                error!("{}", err);
+            }
+        }
+        Ok(Some(Err(err))) => debug!(?err, "couldn't link {}", verification.username),
+        // Completed by the "Verify" button in the meantime, which shows the result itself
+        Ok(None) => debug!("{} was already verified", verification.username),
+        Err(err) => error!("{}", err),
+    }
+}
+
+/// Replaces the code in the `/link` message with the success message.
+async fn edit_message(state: &AppState, verification: &PendingVerification) -> anyhow::Result<()> {
+    let Some(token) = &verification.interaction_token else {
+        return Ok(());
+    };
+
+    let locale: Locale = verification.locale.to_owned().into();
+    let content = success_message(locale, verification.id, &verification.username);
+
+    FlowMessage::finished(state, verification, locale, content)
+        .edit(state, token)
+        .await
+}
@@ -0,0 +1,169 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use reqwest::Client;
+use tokio::{
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, error, warn};
+
+use crate::{scratch::api::ScratchAPIClient, state::AppState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StudioHealth {
+    id: i64,
+    healthy: bool,
+}
+
+/// Verification studios, assigned to pending verifications in turns.
+///
+/// A studio is unhealthy if it was deleted or its comments were turned off.
+#[derive(Debug, Clone)]
+pub struct StudioPool {
+    studios: Arc<RwLock<Vec<StudioHealth>>>,
+    next: Arc<AtomicUsize>,
+}
+
+impl StudioPool {
+    /// All studios are assumed to be healthy until the first check.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ids` is empty.
+    pub fn new(ids: &[i64]) -> Self {
+        assert!(!ids.is_empty(), "no verification studios");
+
+        let studios = ids
+            .iter()
+            .map(|&id| StudioHealth { id, healthy: true })
+            .collect();
+
+        Self {
+            studios: Arc::new(RwLock::new(studios)),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Picks the next healthy studio, or any studio if none of them are healthy.
+    pub fn pick(&self) -> i64 {
+        let studios = self.studios.read().unwrap();
+
+        let mut healthy: Vec<_> = studios.iter().filter(|studio| studio.healthy).collect();
+        if healthy.is_empty() {
+            warn!("no healthy verification studios");
+            healthy = studios.iter().collect();
+        }
+
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        healthy[next % healthy.len()].id
+    }
+
+    /// Until the next check, after a failed fetch of its comments.
+    pub fn mark_unhealthy(&self, id: i64) {
+        self.set_healthy(id, false);
+    }
+
+    fn set_healthy(&self, id: i64, healthy: bool) {
+        let mut studios = self.studios.write().unwrap();
+
+        for studio in studios.iter_mut().filter(|studio| studio.id == id) {
+            if studio.healthy != healthy {
+                let status = if healthy { "healthy" } else { "unhealthy" };
+                warn!("verification studio {id} is now {status}");
+            }
+            studio.healthy = healthy;
+        }
+    }
+
+    pub async fn check(&self, client: &Client) {
+        let ids: Vec<_> = self
+            .studios
+            .read()
+            .unwrap()
+            .iter()
+            .map(|studio| studio.id)
+            .collect();
+
+        for id in ids {
+            match client.get_scratch_api_studio(id).await {
+                Ok(Some(studio)) => self.set_healthy(id, studio.comments_allowed),
+                Ok(None) => self.set_healthy(id, false),
+                // Don't blame the studio if the Scratch API is down
+                Err(err) => error!("failed to check verification studio {id}: {err}"),
+            }
+        }
+    }
+}
+
+pub fn spawn(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(health_check(state))
+}
+
+async fn health_check(state: AppState) -> () {
+    debug!("starting verification studio health check");
+
+    let mut delay = interval(Duration::from_secs(5 * 60));
+    delay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        delay.tick().await;
+
+        state.studios.check(&state.reqwest_client).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_rotates() {
+        let pool = StudioPool::new(&[1, 2, 3]);
+
+        let picked: Vec<_> = (0..6).map(|_| pool.pick()).collect();
+
+        assert_eq!(picked, vec![1, 2, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn pick_skips_unhealthy() {
+        let pool = StudioPool::new(&[1, 2, 3]);
+        pool.set_healthy(2, false);
+
+        let picked: Vec<_> = (0..4).map(|_| pool.pick()).collect();
+
+        assert_eq!(picked, vec![1, 3, 1, 3]);
+    }
+
+    #[test]
+    fn mark_unhealthy_until_check() {
+        let pool = StudioPool::new(&[1, 2]);
+        pool.mark_unhealthy(1);
+
+        assert_eq!(pool.pick(), 2);
+        assert_eq!(pool.pick(), 2);
+
+        pool.set_healthy(1, true);
+
+        let picked: Vec<_> = (0..2).map(|_| pool.pick()).collect();
+
+        assert_eq!(picked, vec![1, 2]);
+    }
+
+    #[test]
+    fn pick_all_unhealthy() {
+        let pool = StudioPool::new(&[1, 2]);
+        pool.set_healthy(1, false);
+        pool.set_healthy(2, false);
+
+        let picked: Vec<_> = (0..2).map(|_| pool.pick()).collect();
+
+        assert_eq!(picked, vec![1, 2]);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{
+    database::PendingVerification,
+    scratch::{
+        api::studio::Comment,
+        cloud::{CloudLog, CLOUD_VARIABLE},
+    },
+};
+
+use super::code::normalize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    InvalidAccount(String),
+    InvalidCode(String),
+    NotFound,
+}
+
+pub fn validate_comment(
+    comments: Vec<Comment>,
+    verification: &PendingVerification,
+) -> Result<(), ValidationError> {
+    let comments: Vec<_> = comments
+        .into_iter()
+        .filter(|comment| comment.datetime_created > verification.generated_at)
+        .collect();
+
+    let code = normalize(&verification.code);
+
+    let valid_code = |comment: &&Comment| normalize(&comment.content) == code;
+    let valid_username = |comment: &&Comment| {
+        comment.author.username.to_lowercase() == verification.username.to_lowercase()
+    };
+
+    if let Some(_) = comments.iter().filter(valid_code).find(valid_username) {
+        Ok(())
+    } else {
+        Err(if let Some(comment) = comments.iter().find(valid_code) {
+            ValidationError::InvalidAccount(comment.author.username.to_string())
+        } else if let Some(comment) = comments.iter().find(valid_username) {
+            ValidationError::InvalidCode(comment.content.to_string())
+        } else {
+            ValidationError::NotFound
+        })
+    }
+}
+
+/// Only the latest value set by the user counts, so that a mistyped code can be corrected.
+pub fn validate_cloud(
+    logs: Vec<CloudLog>,
+    verification: &PendingVerification,
+) -> Result<(), ValidationError> {
+    let logs: Vec<_> = logs
+        .into_iter()
+        .filter(|log| log.timestamp > verification.generated_at)
+        .filter(|log| log.verb == "set_var" && log.name == CLOUD_VARIABLE)
+        .collect();
+
+    let valid_username =
+        |log: &&CloudLog| log.user.to_lowercase() == verification.username.to_lowercase();
+
+    match logs
+        .iter()
+        .filter(valid_username)
+        .max_by_key(|log| log.timestamp)
+    {
+        Some(log) if log.value == verification.code => Ok(()),
+        Some(log) => Err(ValidationError::InvalidCode(log.value.to_string())),
+        None => Err(
+            match logs.iter().find(|log| log.value == verification.code) {
+                Some(log) => ValidationError::InvalidAccount(log.user.to_string()),
+                None => ValidationError::NotFound,
+            },
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::{scratch::api::studio::Author, verification::Method};
+
+    use super::*;
+
+    fn comment(code: &str, username: &str) -> Comment {
+        Comment {
+            id: 225945888,
+            parent_id: None,
+            commentee_id: None,
+            content: code.into(),
+            datetime_created: datetime!(2023-06-08 16:01:00.000 UTC),
+            datetime_modified: datetime!(2023-06-08 16:01:00.000 UTC),
+            visibility: "visible".into(),
+            author: Author {
+                id: 106748322,
+                username: username.into(),
+                scratchteam: false,
+                image: "https://cdn2.scratch.mit.edu/get_image/user/106748322_60x60.png".into(),
+            },
+            reply_count: 0,
+        }
+    }
+
+    fn comments(data: &[(&str, &str)]) -> Vec<Comment> {
+        data.into_iter()
+            .map(|(code, username)| comment(code, username))
+            .collect()
+    }
+
+    fn verification() -> PendingVerification {
+        PendingVerification {
+            id: "755497867606622450".parse().unwrap(),
+            username: "username1".into(),
+            code: "code1".into(),
+            method: Method::Comment,
+            generated_at: datetime!(2023-06-08 16:00:00.000 UTC),
+            expires_at: datetime!(2023-06-08 16:05:00.000 UTC),
+            interaction_token: None,
+            locale: None,
+            studio_id: Some(29137750),
+            guild_id: None,
+        }
+    }
+
+    #[test]
+    fn ok() {
+        let comments = comments(&[
+            ("code1", "username1"),
+            ("code2", "username1"),
+            ("code1", "username2"),
+            ("code2", "username2"),
+        ]);
+
+        let result = validate_comment(comments, &verification());
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn ok_whitespace_and_case() {
+        let comments = comments(&[(" CO de1\n", "username1")]);
+
+        let result = validate_comment(comments, &verification());
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn comment_not_found() {
+        let comments = comments(&[("code2", "username2"), ("code3", "username3")]);
+
+        let result = validate_comment(comments, &verification());
+
+        assert_eq!(result, Err(ValidationError::NotFound));
+    }
+
+    #[test]
+    fn invalid_code() {
+        let comments = comments(&[
+            ("code2", "username1"),
+            ("code2", "username2"),
+            ("code3", "username3"),
+        ]);
+
+        let result = validate_comment(comments, &verification());
+
+        assert_eq!(result, Err(ValidationError::InvalidCode("code2".into())));
+    }
+
+    #[test]
+    fn invalid_account() {
+        let comments = comments(&[
+            ("code1", "username2"),
+            ("code2", "username2"),
+            ("code3", "username3"),
+        ]);
+
+        let result = validate_comment(comments, &verification());
+
+        assert_eq!(
+            result,
+            Err(ValidationError::InvalidAccount("username2".into()))
+        );
+    }
+
+    #[test]
+    fn invalid_both() {
+        let comments = comments(&[
+            ("code1", "username2"),
+            ("code2", "username1"),
+            ("code2", "username2"),
+            ("code3", "username3"),
+        ]);
+
+        let result = validate_comment(comments, &verification());
+
+        assert_eq!(
+            result,
+            Err(ValidationError::InvalidAccount("username2".into()))
+        );
+    }
+
+    #[test]
+    fn too_early() {
+        let comments = comments(&[
+            ("code1", "username1"),
+            ("code2", "username1"),
+            ("code1", "username2"),
+            ("code2", "username2"),
+        ]);
+
+        let verification = PendingVerification {
+            generated_at: datetime!(2023-06-08 17:00:00.000 UTC),
+            expires_at: datetime!(2023-06-08 17:05:00.000 UTC),
+            ..verification()
+        };
+
+        let result = validate_comment(comments, &verification);
+
+        assert_eq!(result, Err(ValidationError::NotFound));
+    }
+
+    fn log(value: &str, user: &str, minute: u8) -> CloudLog {
+        CloudLog {
+            user: user.into(),
+            verb: "set_var".into(),
+            name: CLOUD_VARIABLE.into(),
+            value: value.into(),
+            timestamp: datetime!(2023-06-08 16:00:00.000 UTC)
+                .replace_minute(minute)
+                .unwrap(),
+        }
+    }
+
+    fn logs(data: &[(&str, &str, u8)]) -> Vec<CloudLog> {
+        data.into_iter()
+            .map(|(value, user, minute)| log(value, user, *minute))
+            .collect()
+    }
+
+    fn cloud_verification() -> PendingVerification {
+        PendingVerification {
+            method: Method::Cloud,
+            ..verification()
+        }
+    }
+
+    #[test]
+    fn cloud_ok() {
+        let logs = logs(&[
+            ("code1", "username1", 2),
+            ("code2", "username1", 1),
+            ("code2", "username2", 3),
+        ]);
+
+        let result = validate_cloud(logs, &cloud_verification());
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn cloud_latest_wins() {
+        let logs = logs(&[("code2", "username1", 2), ("code1", "username1", 1)]);
+
+        let result = validate_cloud(logs, &cloud_verification());
+
+        assert_eq!(result, Err(ValidationError::InvalidCode("code2".into())));
+    }
+
+    #[test]
+    fn cloud_invalid_account() {
+        let logs = logs(&[("code2", "username2", 1), ("code1", "username2", 2)]);
+
+        let result = validate_cloud(logs, &cloud_verification());
+
+        assert_eq!(
+            result,
+            Err(ValidationError::InvalidAccount("username2".into()))
+        );
+    }
+
+    #[test]
+    fn cloud_not_found() {
+        let mut logs = logs(&[("code2", "username2", 1)]);
+        logs.push(CloudLog {
+            name: "☁ other".into(),
+            ..log("code1", "username1", 2)
+        });
+
+        let result = validate_cloud(logs, &cloud_verification());
+
+        assert_eq!(result, Err(ValidationError::NotFound));
+    }
+
+    #[test]
+    fn cloud_too_early() {
+        let logs = logs(&[("code1", "username1", 0)]);
+
+        let result = validate_cloud(logs, &cloud_verification());
+
+        assert_eq!(result, Err(ValidationError::NotFound));
+    }
+}